@@ -77,11 +77,11 @@ fn parse_log_level(input: &str) -> Result<log::LevelFilter, &'static str> {
     }
 }
 
-/// Example application for cli_settings
+/// Example application for `cli_settings`
 ///
 /// Load configuration files 'example1.yml' and 'example2.yml' from the current folder,
 /// process the command line arguments and finally display the resulting settings.
-#[derive(Debug)]
+// no derive(Debug): Debug is implemented by cli_settings, to mask the secret fields
 #[cli_settings]
 #[cli_settings_file = "#[serde_with::serde_as]#[derive(serde::Deserialize)]"]
 #[cli_settings_clap = "#[derive(clap::Parser)]#[command(name = \"cli-settings-example\", version)]"]
@@ -119,6 +119,11 @@ pub struct Settings {
     #[cli_settings_file = "#[serde_as(as = \"Option<serde_with::DisplayFromStr>\")]"]
     pub log: log::LevelFilter,
 
+    /// token of the remote API, settable only from config file, displayed as ***
+    #[cli_settings_file]
+    #[cli_settings_secret]
+    pub api_token: String,
+
     #[cli_settings_mandatory]
     #[cli_settings_default = "CliCommand::Show"]
     #[cli_settings_clap = "#[command(subcommand)]"]
@@ -10,10 +10,328 @@ use syn::{parse_macro_input, spanned::Spanned};
 /// `cli_settings_file`: config file related attributes
 /// `cli_settings_clap`: command line related attributes
 /// `cli_settings_mandatory`: indicate a mandatory CLI argument (presence/absence only, no associated value)
+/// `cli_settings_env`: environment variable related attributes (variable name)
+/// `cli_settings_env_prefix`: prefix of the environment variable names (struct only)
+/// `cli_settings_format`: default format of the configuration files (struct only)
+/// `cli_settings_formats`: formats of the configuration files supported by the generated code (struct only)
+/// `cli_settings_serialize`: generate the serialization of the file settings (struct only)
+/// `cli_settings_nested`: nested settings field, or nested section struct with its command line prefix
+/// `cli_settings_merge`: merge strategy of a collection field set by several sources
+/// `cli_settings_strict`: handling of the unknown keys in the configuration files (struct only)
+/// `cli_settings_validate`: list of checks of the field value, after all sources are merged
+/// `cli_settings_validate_with`: method checking the whole settings, after all sources are merged (struct only)
+/// `cli_settings_app`: application name, to discover the configuration files (struct only)
+/// `cli_settings_config_arg`: generate the `--config` and `--no-config` arguments (struct only)
+/// `cli_settings_include`: key of the configuration files listing the files to include (struct only)
+/// `cli_settings_secret`: secret field, masked in the debug output and omitted from the configuration dumps
+/// `cli_settings_diff`: generate the comparison of the settings, field by field (struct only)
+/// `cli_settings_watch`: generate the watch of the configuration files, with its polling period (struct only)
+/// `cli_settings_watch_sighup`: build the watched settings again on SIGHUP (struct only)
+/// `cli_settings_interpolate`: expand the references in the string values of the configuration files (struct only)
 /// 'doc': doc related attributes
 /// '_': other attributes
 type AttrMap = std::collections::HashMap<String, proc_macro2::TokenStream>;
 
+/// Start of the error message replacing the error of the format when the value of a secret field is invalid,
+/// not to output the value; followed by the key of the field and a quote
+const SECRET_ERROR: &str = "invalid value of the secret field '";
+
+/// Attributes whose value is kept as a string literal instead of being parsed as tokens
+const STR_ATTRIBUTES: &[&str] = &[
+    "cli_settings_env",
+    "cli_settings_env_prefix",
+    "cli_settings_format",
+    "cli_settings_formats",
+    "cli_settings_nested",
+    "cli_settings_merge",
+    "cli_settings_strict",
+    "cli_settings_app",
+    "cli_settings_config_arg",
+    "cli_settings_include",
+    "cli_settings_watch",
+];
+
+/// Struct attributes that do not apply to a nested section
+const SECTION_UNSUPPORTED: &[&str] = &[
+    "cli_settings_formats",
+    "cli_settings_strict",
+    "cli_settings_validate_with",
+    "cli_settings_app",
+    "cli_settings_config_arg",
+    "cli_settings_include",
+    "cli_settings_watch",
+    "cli_settings_watch_sighup",
+    "cli_settings_interpolate",
+];
+
+/// Get the string value of an attribute stored as a string literal, if any
+fn attr_str(attrs: &AttrMap, key: &str) -> Result<Option<String>, syn::Error> {
+    match attrs.get(key) {
+        Some(tokens) if !tokens.is_empty() => {
+            Ok(Some(syn::parse2::<syn::LitStr>(tokens.clone())?.value()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Configuration file format
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Yaml,
+    Toml,
+    Json,
+    Json5,
+}
+
+impl Format {
+    /// All known formats
+    const ALL: &'static [Self] = &[Self::Yaml, Self::Toml, Self::Json, Self::Json5];
+
+    /// Name of the format, as used in `cli_settings_format` and in the cargo features
+    fn name(self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+            Self::Json => "json",
+            Self::Json5 => "json5",
+        }
+    }
+
+    /// Name of the variant of the generated format enum
+    fn variant(self) -> syn::Ident {
+        let name = match self {
+            Self::Yaml => "Yaml",
+            Self::Toml => "Toml",
+            Self::Json => "Json",
+            Self::Json5 => "Json5",
+        };
+        syn::Ident::new(name, proc_macro2::Span::call_site())
+    }
+
+    /// File extensions associated to the format
+    fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Yaml => &["yml", "yaml"],
+            Self::Toml => &["toml"],
+            Self::Json => &["json"],
+            Self::Json5 => &["json5"],
+        }
+    }
+
+    /// Whether the support of the format is enabled
+    fn enabled(self) -> bool {
+        match self {
+            Self::Yaml => cfg!(feature = "yaml"),
+            Self::Toml => cfg!(feature = "toml"),
+            Self::Json => cfg!(feature = "json"),
+            Self::Json5 => cfg!(feature = "json5"),
+        }
+    }
+
+    /// Output the expression parsing `content` into the expected type,
+    /// with the error converted to a parse error including the location if available
+    fn output_parse(self) -> proc_macro2::TokenStream {
+        match self {
+            Self::Yaml => quote! {
+                serde_yaml::from_str(content).map_err(|err| {
+                    let location = err.location().map(|l| (l.line(), l.column()));
+                    parse_error(path, location, err)
+                })
+            },
+            Self::Toml => quote! {
+                toml::from_str(content).map_err(|err| {
+                    let location = err.span().map(|span| text_location(content, span.start));
+                    parse_error(path, location, err)
+                })
+            },
+            Self::Json => quote! {
+                serde_json::from_str(content).map_err(|err| {
+                    let location = Some((err.line(), err.column()));
+                    parse_error(path, location, err)
+                })
+            },
+            Self::Json5 => quote! {
+                json5::from_str(content).map_err(|err| {
+                    let json5::Error::Message { location, .. } = &err;
+                    let location = location.as_ref().map(|l| (l.line, l.column));
+                    parse_error(path, location, err)
+                })
+            },
+        }
+    }
+
+    /// Comment prefix of the format, if comments are supported
+    fn comment(self) -> Option<&'static str> {
+        match self {
+            Self::Yaml | Self::Toml => Some("#"),
+            Self::Json => None,
+            Self::Json5 => Some("//"),
+        }
+    }
+
+    /// Output the expression serializing `file_config` into a string
+    fn output_serialize(self) -> proc_macro2::TokenStream {
+        match self {
+            Self::Yaml => quote! { serde_yaml::to_string(file_config).map_err(serialize_error) },
+            Self::Toml => quote! { toml::to_string(file_config).map_err(serialize_error) },
+            Self::Json => {
+                quote! { serde_json::to_string_pretty(file_config).map_err(serialize_error) }
+            }
+            Self::Json5 => quote! { json5::to_string(file_config).map_err(serialize_error) },
+        }
+    }
+}
+
+/// Get the lines of the doc comments, without the `///` prefix
+fn doc_lines(attrs: &AttrMap) -> Vec<String> {
+    let Some(doc) = attrs.get("doc") else {
+        return vec![];
+    };
+    syn::parse::Parser::parse2(syn::Attribute::parse_outer, doc.clone())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(l),
+                        ..
+                    }),
+                ..
+            }) => Some(l.value()),
+            _ => None,
+        })
+        .flat_map(|doc| doc.split('\n').map(str::to_string).collect::<Vec<_>>())
+        .collect()
+}
+
+/// Merge strategy of a field, when its value is provided by several sources
+#[derive(Clone, Copy, PartialEq)]
+enum Merge {
+    Replace, // the last value replaces the previous one
+    Append,  // the items of the last value are added after the previous ones
+    Prepend, // the items of the last value are added before the previous ones
+    Union, // the items of the last value are added after the previous ones, if not already present
+    Deep, // the entries of the last map value replace the previous ones key by key, null removes a key
+}
+
+impl Merge {
+    /// Get the merge strategy from its name
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "replace" => Some(Self::Replace),
+            "append" => Some(Self::Append),
+            "prepend" => Some(Self::Prepend),
+            "union" => Some(Self::Union),
+            "deep" => Some(Self::Deep),
+            _ => None,
+        }
+    }
+}
+
+/// Handling of the keys of a configuration file that match no field
+#[derive(Clone, Copy, PartialEq)]
+enum Strict {
+    Ignore, // unknown keys are silently ignored
+    Error,  // the first unknown key is an error
+    Warn,   // unknown keys are reported in the sources
+}
+
+/// Check of a field value, once all the sources are merged
+enum Check {
+    NonEmpty,              // `non_empty`: the value shall not be empty
+    PathExists,            // `path_exists`: the value shall be an existing path
+    Range(syn::Expr),      // `range(a..=b)`: the value shall be in the range
+    OneOf(Vec<syn::Expr>), // `one_of(a, b)`: the value shall be one of the listed values
+    Custom(syn::ExprPath), // `path::to::fn`: user function returning `Result<(), E>`
+}
+
+impl Check {
+    /// Parse the list of checks of `cli_settings_validate`
+    fn parse_list(tokens: proc_macro2::TokenStream) -> Result<Vec<Self>, syn::Error> {
+        let exprs = syn::parse::Parser::parse2(
+            syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated,
+            tokens,
+        )?;
+        exprs.into_iter().map(Self::parse).collect()
+    }
+
+    /// Parse one check
+    fn parse(expr: syn::Expr) -> Result<Self, syn::Error> {
+        match expr {
+            syn::Expr::Path(p) if p.path.is_ident("non_empty") => Ok(Self::NonEmpty),
+            syn::Expr::Path(p) if p.path.is_ident("path_exists") => Ok(Self::PathExists),
+            syn::Expr::Path(p) => Ok(Self::Custom(p)),
+            syn::Expr::Call(c) => match &*c.func {
+                syn::Expr::Path(p) if p.path.is_ident("range") && c.args.len() == 1 => {
+                    Ok(Self::Range(c.args[0].clone()))
+                }
+                syn::Expr::Path(p) if p.path.is_ident("one_of") && !c.args.is_empty() => {
+                    Ok(Self::OneOf(c.args.into_iter().collect()))
+                }
+                _ => Err(syn::Error::new(c.span(), "unknown check, expected range(a..=b) or one_of(a, b)")),
+            },
+            _ => Err(syn::Error::new(
+                expr.span(),
+                "unknown check, expected non_empty, path_exists, range(a..=b), one_of(a, b) or a function path",
+            )),
+        }
+    }
+
+    /// Output the check of the field value `cfg.<field>`, returning an error if invalid
+    fn output(&self, field: &Field<'_>) -> proc_macro2::TokenStream {
+        let ident = field.ident;
+        let name = field.name();
+        let (valid, reason) = match self {
+            Self::NonEmpty => (
+                quote! { !cfg.#ident.is_empty() },
+                "shall not be empty".to_string(),
+            ),
+            Self::PathExists => (
+                quote! { std::path::Path::new(&cfg.#ident).exists() },
+                "shall be an existing path".to_string(),
+            ),
+            Self::Range(range) => (
+                quote! { (#range).contains(&cfg.#ident) },
+                format!(
+                    "shall be in the range {}",
+                    range.to_token_stream().to_string().replace(' ', "")
+                ),
+            ),
+            Self::OneOf(values) => (
+                quote! { [#(#values),*].iter().any(|value| cfg.#ident == *value) },
+                format!(
+                    "shall be one of {}",
+                    values
+                        .iter()
+                        .map(|v| v.to_token_stream().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ),
+            Self::Custom(path) => {
+                return quote! {
+                    if let Err(err) = #path(&cfg.#ident) {
+                        return Err(invalid(#name, sources, err));
+                    }
+                };
+            }
+        };
+        quote! {
+            if !(#valid) {
+                return Err(invalid(#name, sources, #reason));
+            }
+        }
+    }
+}
+
+/// Handling of the configuration files given by the `--config` argument
+#[derive(Clone, Copy, PartialEq)]
+enum ConfigArg {
+    Replace, // the given files replace the configuration files passed to `build()`
+    Extend,  // the given files are loaded after the configuration files passed to `build()`
+}
+
 /// Field element, quite similar to `syn::Field`, keeping only the relevant fields
 struct Field<'a> {
     attrs: AttrMap,           // classified attributes of the field
@@ -21,13 +339,253 @@ struct Field<'a> {
     ident: &'a syn::Ident,    // field name
     ty: &'a syn::Type,        // field type
     opt: bool,                // whether the type shall be converted to Option<ty>
+    env: Option<String>,      // name of the associated environment variable
+    nested: bool,             // whether the field is a nested settings struct
+    merge: Merge,             // merge strategy of the field
+    checks: Vec<Check>,       // checks of the field value
+    secret: bool,             // whether the field value shall not be output
+}
+
+impl Field<'_> {
+    /// Name of the field, as a string
+    fn name(&self) -> String {
+        syn::ext::IdentExt::unraw(self.ident).to_string()
+    }
+
+    /// Key of the field in the configuration files, renamed by serde if requested
+    fn file_key(&self) -> Option<String> {
+        let file = self.attrs.get("cli_settings_file")?;
+        let name = self.name();
+        Some(attr_key_value(file.clone(), "rename", &name).unwrap_or(name))
+    }
+
+    /// Whether the file struct deserializes the field with `deserialize_secret()`, hiding the value in the
+    /// errors: a secret field without its own deserialization attribute
+    fn has_secret_deserialize(&self) -> bool {
+        self.secret
+            && self.attrs.get("cli_settings_file").is_some_and(|file| {
+                let file = file.to_string();
+                !file.contains("with") && !file.contains("serde_as")
+            })
+    }
+
+    /// Whether the value of the field can also be read from a file, for a secret field
+    fn has_secret_file(&self) -> bool {
+        self.secret && self.opt
+    }
+
+    /// Field of the file or clap struct holding the path of the file containing the secret value
+    fn secret_file_ident(&self) -> syn::Ident {
+        syn::Ident::new(
+            &format!("cli_settings_{}_file", self.name()),
+            self.ident.span(),
+        )
+    }
+
+    /// Output the reading of the secret value from the file at `path`, as a `Result`
+    fn output_read_secret(&self) -> proc_macro2::TokenStream {
+        let ty = self.ty;
+        quote! {
+            match std::fs::read_to_string(&path) {
+                Ok(content) => content
+                    .trim()
+                    .parse::<#ty>()
+                    .map_err(|err| -> Box<dyn std::error::Error + Send + Sync> { err.to_string().into() }),
+                Err(err) => Err(err.into()),
+            }
+        }
+    }
+
+    /// Type of the field in the file or clap struct, for a nested settings struct
+    /// (type alias generated for the nested struct, in the same module)
+    fn nested_ty(&self, prefix: &str) -> syn::Type {
+        let mut ty = self.ty.clone();
+        if let syn::Type::Path(syn::TypePath { path, .. }) = &mut ty {
+            if let Some(last) = path.segments.last_mut() {
+                last.ident = nested_alias_ident(prefix, &last.ident);
+            }
+        }
+        ty
+    }
+
+    /// Whether the field type is written with its key and value types, as `Map<K, V>`
+    fn has_map_args(&self) -> bool {
+        match self.ty {
+            syn::Type::Path(syn::TypePath { path, .. }) => match path.segments.last() {
+                Some(syn::PathSegment {
+                    arguments: syn::PathArguments::AngleBracketed(args),
+                    ..
+                }) => {
+                    args.args
+                        .iter()
+                        .filter(|arg| matches!(arg, syn::GenericArgument::Type(_)))
+                        .count()
+                        >= 2
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Whether the name of the field type designates a map, such as `HashMap` or `BTreeMap`
+    fn is_map_name(&self) -> bool {
+        match self.ty {
+            syn::Type::Path(syn::TypePath { path, .. }) => path
+                .segments
+                .last()
+                .is_some_and(|last| last.ident.to_string().ends_with("Map")),
+            _ => false,
+        }
+    }
+
+    /// Type of the field in the file struct, for a deep merged map: `Map<K, Option<V>>`
+    fn deep_ty(&self) -> syn::Type {
+        let mut ty = self.ty.clone();
+        if let syn::Type::Path(syn::TypePath { path, .. }) = &mut ty {
+            if let Some(syn::PathArguments::AngleBracketed(args)) =
+                path.segments.last_mut().map(|last| &mut last.arguments)
+            {
+                if let Some(syn::GenericArgument::Type(value_ty)) = args.args.iter_mut().nth(1) {
+                    *value_ty = syn::parse_quote! { Option<#value_ty> };
+                }
+            }
+        }
+        ty
+    }
+
+    /// Output the deep merge of the field in `cfg` with `value` read from a file, a null entry removing the key
+    fn output_deep_merge(&self, value: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let ident = self.ident;
+        quote! {
+            for (key, item) in #value {
+                match item {
+                    Some(item) => {
+                        cfg.#ident.insert(key, item);
+                    }
+                    None => {
+                        cfg.#ident.remove(&key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Output the update of the field in `cfg` with `value`, according to the merge strategy
+    fn output_merge(&self, value: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let ident = self.ident;
+        match self.merge {
+            Merge::Replace => quote! { cfg.#ident = #value; },
+            Merge::Append | Merge::Deep => quote! { cfg.#ident.extend(#value); },
+            Merge::Prepend => quote! {
+                let mut value = #value;
+                value.extend(std::mem::take(&mut cfg.#ident));
+                cfg.#ident = value;
+            },
+            Merge::Union => {
+                // a missing `contains()` is reported on the field type
+                let contains =
+                    quote::quote_spanned! { self.ty.span()=> cfg.#ident.contains(&item) };
+                quote! {
+                    for item in #value {
+                        if !#contains {
+                            cfg.#ident.extend(std::iter::once(item));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Identifier of the type alias of the file or clap struct of a nested settings struct
+fn nested_alias_ident(prefix: &str, ident: &syn::Ident) -> syn::Ident {
+    let name = format!("_CliSettings{prefix}{ident}");
+    syn::Ident::new(&name, ident.span())
+}
+
+/// Convert a `CamelCase` identifier to `snake_case`
+fn to_snake_case(name: &str) -> String {
+    let mut res = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                res.push('_');
+            }
+            res.extend(c.to_lowercase());
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
+
+/// Find the value of the given key in attributes (`key` or `key = "value"`),
+/// `default` if the key has no value, `None` if the key is absent
+fn attr_key_value(tokens: proc_macro2::TokenStream, key: &str, default: &str) -> Option<String> {
+    let mut res = None;
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            proc_macro2::TokenTree::Group(g) => {
+                res = res.or(attr_key_value(g.stream(), key, default));
+            }
+            proc_macro2::TokenTree::Ident(i) if i == key => {
+                let mut name = default.to_string();
+                if matches!(tokens.peek(), Some(proc_macro2::TokenTree::Punct(p)) if p.as_char() == '=')
+                {
+                    tokens.next();
+                    if let Some(proc_macro2::TokenTree::Literal(l)) = tokens.next() {
+                        if let Ok(l) = syn::parse2::<syn::LitStr>(l.into_token_stream()) {
+                            name = l.value();
+                        }
+                    }
+                }
+                res = Some(name);
+            }
+            _ => (),
+        }
+    }
+    res
+}
+
+/// Check that the derive attributes do not include `Debug`, implemented by the macro
+/// (only the attributes following `#[cli_settings]` are given to the macro)
+fn check_no_derive_debug(tokens: proc_macro2::TokenStream) -> Result<(), syn::Error> {
+    let attrs = syn::parse::Parser::parse2(syn::Attribute::parse_outer, tokens)?;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        let paths = attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+        )?;
+        if let Some(debug) = paths
+            .iter()
+            .find(|p| p.segments.last().is_some_and(|s| s.ident == "Debug"))
+        {
+            return Err(syn::Error::new(
+                debug.span(),
+                "remove Debug from the derive: with secret fields, cli_settings implements Debug to mask them",
+            ));
+        }
+    }
+    Ok(())
 }
 
 /// Container for the whole settings struct
 struct SettingStruct<'a> {
-    s: &'a syn::ItemStruct, // associated syn::ItemStruct object
-    attrs: AttrMap,         // classified attributes of the struct
-    fields: Vec<Field<'a>>, // list of fields
+    s: &'a syn::ItemStruct,                // associated syn::ItemStruct object
+    attrs: AttrMap,                        // classified attributes of the struct
+    fields: Vec<Field<'a>>,                // list of fields
+    formats: Vec<Format>, // formats of the configuration files supported by the generated code
+    format: Option<Format>, // default format of the configuration files
+    section: bool,        // whether the struct is a nested section of another settings struct
+    clap_prefix: Option<String>, // prefix of the long command line arguments, for a nested section
+    strict: Strict,       // handling of the unknown keys in the configuration files
+    app: Option<String>,  // application name, to discover the configuration files
+    config_arg: Option<(ConfigArg, bool)>, // generation of the `--config` argument, with the short flag `-c` or not
+    include: Option<String>, // key of the configuration files listing the files to include
+    watch: Option<u64>, // polling period of the configuration files in milliseconds, if watched
+    sighup: bool,       // whether SIGHUP builds the watched settings again
+    interpolate: bool, // whether the references in the string values of the configuration files are expanded
 }
 
 impl<'a> SettingStruct<'a> {
@@ -37,6 +595,21 @@ impl<'a> SettingStruct<'a> {
             s,
             attrs: AttrMap::default(),
             fields: vec![],
+            formats: Format::ALL
+                .iter()
+                .copied()
+                .filter(|f| f.enabled())
+                .collect(),
+            format: None,
+            section: false,
+            clap_prefix: None,
+            strict: Strict::Ignore,
+            app: None,
+            config_arg: None,
+            include: None,
+            watch: None,
+            sighup: false,
+            interpolate: false,
         };
 
         let syn::Fields::Named(fields) = &s.fields else {
@@ -48,26 +621,314 @@ impl<'a> SettingStruct<'a> {
 
         // struct attributes
         ss.attrs = Self::classify_attributes(&s.attrs)?;
+        let env_prefix = attr_str(&ss.attrs, "cli_settings_env_prefix")?;
+        ss.build_options()?;
+        ss.build_formats()?;
 
         // fields
         ss.fields.reserve_exact(fields.named.len());
         for field in &fields.named {
-            let mut f = Field {
-                attrs: Self::classify_attributes(&field.attrs)?,
-                vis: &field.vis,
-                ident: field.ident.as_ref().ok_or_else(|| {
-                    syn::Error::new(field.span(), "only named fields are supported")
-                })?,
-                ty: &field.ty,
-                opt: false,
-            };
-            f.opt = !f.attrs.contains_key("cli_settings_mandatory");
+            let f = ss.build_field(field, env_prefix.as_deref())?;
             ss.fields.push(f);
         }
+        ss.check_nested_clap()?;
+
+        // Debug is implemented by the macro, not to output the secret fields
+        if ss.has_secrets() {
+            if let Some(attrs) = ss.attrs.get("_") {
+                check_no_derive_debug(attrs.clone())?;
+            }
+        }
 
         Ok(ss)
     }
 
+    /// Get the supported formats and the default format of the configuration files from the struct attributes
+    fn build_formats(&mut self) -> Result<(), syn::Error> {
+        if let Some(formats) = self.attrs.get("cli_settings_formats") {
+            let span = formats.span();
+            let names = attr_str(&self.attrs, "cli_settings_formats")?.unwrap_or_default();
+            let listed = names
+                .split(',')
+                .map(|name| Self::enabled_format(name.trim(), span))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.formats.retain(|f| listed.contains(f));
+        }
+        if self.formats.is_empty() {
+            return Err(syn::Error::new(
+                self.s.span(),
+                "at least one configuration file format feature of cli-settings-derive shall be enabled",
+            ));
+        }
+        self.format = self.formats.first().copied();
+        if let Some(format) = attr_str(&self.attrs, "cli_settings_format")? {
+            if format == "none" {
+                self.format = None;
+            } else {
+                let span = self.attrs["cli_settings_format"].span();
+                let format = Self::enabled_format(&format, span)?;
+                if !self.formats.contains(&format) {
+                    return Err(syn::Error::new(
+                        span,
+                        format!(
+                            "the {} format is not listed in cli_settings_formats",
+                            format.name()
+                        ),
+                    ));
+                }
+                self.format = Some(format);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get a configuration file format from its name, checking that its feature is enabled
+    fn enabled_format(name: &str, span: proc_macro2::Span) -> Result<Format, syn::Error> {
+        let format = *Format::ALL
+            .iter()
+            .find(|f| f.name() == name)
+            .ok_or_else(|| syn::Error::new(span, "unknown configuration file format"))?;
+        if !format.enabled() {
+            return Err(syn::Error::new(
+                span,
+                format!("the {name} format requires the '{name}' feature of cli-settings-derive"),
+            ));
+        }
+        Ok(format)
+    }
+
+    /// Get the struct level options from the struct attributes
+    fn build_options(&mut self) -> Result<(), syn::Error> {
+        self.section = self.attrs.contains_key("cli_settings_nested");
+        self.clap_prefix = attr_str(&self.attrs, "cli_settings_nested")?;
+        if self.section {
+            if let Some(key) = SECTION_UNSUPPORTED
+                .iter()
+                .find(|key| self.attrs.contains_key(**key))
+            {
+                return Err(syn::Error::new(
+                    self.attrs[*key].span(),
+                    format!("{key} is not supported in a nested section, annotate the enclosing struct instead"),
+                ));
+            }
+        }
+        if let Some(app) = self.attrs.get("cli_settings_app") {
+            self.app = attr_str(&self.attrs, "cli_settings_app")?;
+            if self.app.as_ref().is_none_or(String::is_empty) {
+                return Err(syn::Error::new(
+                    app.span(),
+                    "cli_settings_app requires the application name",
+                ));
+            }
+        }
+        if self.attrs.contains_key("cli_settings_include") {
+            self.include = Some(
+                attr_str(&self.attrs, "cli_settings_include")?
+                    .unwrap_or_else(|| "include".to_string()),
+            );
+        }
+        self.interpolate = self.attrs.contains_key("cli_settings_interpolate");
+        if let Some(watch) = self.attrs.get("cli_settings_watch") {
+            let period = attr_str(&self.attrs, "cli_settings_watch")?;
+            self.watch = Some(match period {
+                None => 1000,
+                Some(period) => period.parse().map_err(|_| {
+                    syn::Error::new(
+                        watch.span(),
+                        "cli_settings_watch requires a polling period in milliseconds",
+                    )
+                })?,
+            });
+        }
+        if let Some(sighup) = self.attrs.get("cli_settings_watch_sighup") {
+            if self.watch.is_none() {
+                return Err(syn::Error::new(
+                    sighup.span(),
+                    "cli_settings_watch_sighup requires cli_settings_watch",
+                ));
+            }
+            self.sighup = true;
+        }
+        if let Some(config_arg) = self.attrs.get("cli_settings_config_arg") {
+            let (mut mode, mut short) = (ConfigArg::Replace, false);
+            let value = attr_str(&self.attrs, "cli_settings_config_arg")?.unwrap_or_default();
+            for option in value.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+                match option {
+                    "replace" => mode = ConfigArg::Replace,
+                    "extend" => mode = ConfigArg::Extend,
+                    "short" => short = true,
+                    _ => {
+                        return Err(syn::Error::new(
+                            config_arg.span(),
+                            "cli_settings_config_arg shall list \"replace\" or \"extend\", and optionally \"short\"",
+                        ))
+                    }
+                }
+            }
+            self.config_arg = Some((mode, short));
+        }
+        if let Some(strict) = self.attrs.get("cli_settings_strict") {
+            self.strict = match attr_str(&self.attrs, "cli_settings_strict")?.as_deref() {
+                None => Strict::Error,
+                Some("warn") => Strict::Warn,
+                Some(_) => {
+                    return Err(syn::Error::new(
+                        strict.span(),
+                        "cli_settings_strict shall have no value or the value \"warn\"",
+                    ))
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// Get the merge strategy of a field, checking that it applies to the field type
+    fn build_merge(f: &mut Field<'_>, field: &syn::Field) -> Result<(), syn::Error> {
+        if let Some(merge) = f.attrs.get("cli_settings_merge") {
+            let name = attr_str(&f.attrs, "cli_settings_merge")?.unwrap_or_default();
+            f.merge = Merge::from_name(&name).ok_or_else(|| {
+                syn::Error::new(
+                    merge.span(),
+                    "cli_settings_merge shall be one of \"replace\", \"append\", \"prepend\", \"union\" or \"deep\"",
+                )
+            })?;
+            if f.nested {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "cli_settings_merge is not supported for nested settings",
+                ));
+            }
+            if f.merge == Merge::Deep && !f.has_map_args() {
+                return Err(syn::Error::new(
+                    field.ty.span(),
+                    "cli_settings_merge = \"deep\" requires a map type written with its key and value types, \
+                     such as HashMap<K, V>, not a type alias",
+                ));
+            }
+            if f.merge == Merge::Union && f.is_map_name() {
+                return Err(syn::Error::new(
+                    field.ty.span(),
+                    "cli_settings_merge = \"union\" does not apply to a map, use \"append\" or \"deep\"",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Build `Field` from a `syn::Field`
+    fn build_field(
+        &self,
+        field: &'a syn::Field,
+        env_prefix: Option<&str>,
+    ) -> Result<Field<'a>, syn::Error> {
+        let mut f = Field {
+            attrs: Self::classify_attributes(&field.attrs)?,
+            vis: &field.vis,
+            ident: field
+                .ident
+                .as_ref()
+                .ok_or_else(|| syn::Error::new(field.span(), "only named fields are supported"))?,
+            ty: &field.ty,
+            opt: false,
+            env: None,
+            nested: false,
+            merge: Merge::Replace,
+            checks: Vec::new(),
+            secret: false,
+        };
+        f.opt = !f.attrs.contains_key("cli_settings_mandatory");
+        f.nested = f.attrs.contains_key("cli_settings_nested");
+        f.secret = f.attrs.contains_key("cli_settings_secret");
+        if f.nested && f.secret {
+            return Err(syn::Error::new(
+                field.span(),
+                "cli_settings_secret is not supported for nested settings, annotate the fields of the section instead",
+            ));
+        }
+        if f.nested && !f.opt {
+            return Err(syn::Error::new(
+                field.span(),
+                "cli_settings_mandatory is not supported for nested settings",
+            ));
+        }
+        Self::build_merge(&mut f, field)?;
+        if let Some(validate) = f.attrs.get("cli_settings_validate") {
+            if self.section {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "cli_settings_validate is not supported in a nested section, validate the nested field instead",
+                ));
+            }
+            f.checks = Check::parse_list(validate.clone())?;
+        }
+        if f.nested && !matches!(f.ty, syn::Type::Path(_)) {
+            return Err(syn::Error::new(
+                field.ty.span(),
+                "cli_settings_nested requires the type of a struct annotated with cli_settings",
+            ));
+        }
+        if f.attrs.contains_key("cli_settings_env") {
+            if !cfg!(feature = "env") {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "cli_settings_env requires the 'env' feature of cli-settings-derive",
+                ));
+            }
+            if self.section || f.nested {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "cli_settings_env is not supported for nested settings",
+                ));
+            }
+            if f.merge != Merge::Replace {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "cli_settings_env is not supported with cli_settings_merge, the value of the variable cannot be split into items",
+                ));
+            }
+            f.env = match (attr_str(&f.attrs, "cli_settings_env")?, env_prefix) {
+                (Some(name), _) => Some(name),
+                (None, Some(prefix)) => {
+                    Some(format!("{prefix}{}", f.name().to_uppercase()))
+                }
+                (None, None) => {
+                    return Err(syn::Error::new(
+                        field.span(),
+                        "cli_settings_env requires a variable name or a struct level cli_settings_env_prefix",
+                    ))
+                }
+            };
+        }
+        Ok(f)
+    }
+
+    /// Check that a section type is flattened on the command line by one field only,
+    /// the arguments of the section being the same for all its fields
+    fn check_nested_clap(&self) -> Result<(), syn::Error> {
+        let clap_fields = self
+            .fields
+            .iter()
+            .filter(|f| f.nested && f.attrs.contains_key("cli_settings_clap"))
+            .collect::<Vec<_>>();
+        for (i, f) in clap_fields.iter().enumerate() {
+            let ty = f.ty.to_token_stream().to_string();
+            if let Some(first) = clap_fields[..i]
+                .iter()
+                .find(|first| first.ty.to_token_stream().to_string() == ty)
+            {
+                return Err(syn::Error::new(
+                    f.ident.span(),
+                    format!(
+                        "the arguments of this nested section are already on the command line for the field `{}`, \
+                         cli_settings_clap is supported for one field of a section type only",
+                        first.name()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Classify a list of attributes, related to file , clap, or other
     fn classify_attributes(attrs: &'a Vec<syn::Attribute>) -> Result<AttrMap, syn::Error> {
         let mut res = AttrMap::default();
@@ -96,9 +957,14 @@ impl<'a> SettingStruct<'a> {
                             lit: syn::Lit::Str(l),
                         })) = value
                         {
-                            res.entry(path_ident_str)
-                                .or_default()
-                                .extend(proc_macro2::TokenStream::from_str(&l.value())?);
+                            if STR_ATTRIBUTES.contains(&path_ident_str.as_str()) {
+                                // keep the string literal as is
+                                res.insert(path_ident_str, l.to_token_stream());
+                            } else {
+                                res.entry(path_ident_str)
+                                    .or_default()
+                                    .extend(proc_macro2::TokenStream::from_str(&l.value())?);
+                            }
                         } else {
                             return Err(syn::Error::new(attr.span(), "invalid attribute format"));
                         }
@@ -129,6 +995,9 @@ impl<'a> SettingStruct<'a> {
             .collect::<Vec<_>>();
         let vis = if prefix.is_empty() {
             self.s.vis.to_token_stream()
+        } else if self.section {
+            // reachable from the module of the enclosing settings struct
+            quote! { pub }
         } else {
             empty.clone()
         };
@@ -145,203 +1014,3040 @@ impl<'a> SettingStruct<'a> {
                 } else {
                     true
                 }
-            })
+            })
+            .map(|f| {
+                // field tokens
+                let field_attrs = attr_keys
+                    .iter()
+                    .filter(|k| !(f.nested && prefix == "Clap" && **k == "doc"))
+                    .map(|k| f.attrs.get(*k).unwrap_or(&empty))
+                    .collect::<Vec<_>>();
+                let field_vis = f.vis;
+                let field_ident = f.ident;
+                let field_ty = if f.nested && !prefix.is_empty() {
+                    f.nested_ty(prefix).into_token_stream()
+                } else if f.merge == Merge::Deep && prefix == "File" {
+                    f.deep_ty().into_token_stream()
+                } else {
+                    f.ty.into_token_stream()
+                };
+                // flatten the arguments of a nested struct, or prefix the long arguments of a section
+                let field_arg = if prefix != "Clap" {
+                    empty.clone()
+                } else if f.nested {
+                    quote! { #[command(flatten)] }
+                } else {
+                    self.output_clap_prefix(f)
+                };
+                let (field_ty_start, field_ty_end) = if prefix.is_empty()
+                    || !f.opt
+                    || (f.nested && prefix == "Clap")
+                {
+                    // no prefix, field with configured type
+                    (empty.clone(), empty.clone())
+                } else {
+                    // prefix, field with Option<configured type>
+                    (
+                        proc_macro2::TokenStream::from_str("Option<").unwrap(),
+                        proc_macro2::TokenStream::from_str(">").unwrap(),
+                    )
+                };
+                // skip absent values when serializing the file struct
+                let field_skip = if prefix == "File"
+                    && f.opt
+                    && self.attrs.contains_key("cli_settings_serialize")
+                {
+                    quote! { #[serde(skip_serializing_if = "Option::is_none")] }
+                } else {
+                    empty.clone()
+                };
+                let field_secret = Self::output_secret_deserialize_attr(prefix, f);
+                let secret_file = self.output_secret_file_field(prefix, f);
+                // struct tokens
+                        // output one field (without separator)
+                quote! {
+                    #(#field_attrs)* #field_arg #field_skip #field_secret #field_vis #field_ident: #field_ty_start #field_ty #field_ty_end
+                    #secret_file
+                }
+            })
+            .collect::<Vec<_>>();
+        // arguments selecting the configuration files
+        let config_arg = if prefix == "Clap" && self.config_arg.is_some() {
+            let short = if matches!(self.config_arg, Some((_, true))) {
+                quote! { short = 'c', }
+            } else {
+                empty.clone()
+            };
+            quote! {
+                /// Configuration file to load (can be repeated)
+                #[arg(id = "config", #short long = "config", value_name = "PATH")]
+                cli_settings_config: Vec<std::path::PathBuf>,
+                /// Do not load any configuration file
+                #[arg(id = "no-config", long = "no-config", conflicts_with = "config")]
+                cli_settings_no_config: bool,
+            }
+        } else {
+            empty.clone()
+        };
+        // output the whole struct
+        quote! {
+            #(#attrs)* #vis #struct_token #ident
+            {
+                #config_arg
+                #(#fields),*
+            }
+        }
+    }
+
+    /// Output the deserialization attribute of a secret field of the file struct, hiding its value in the errors
+    fn output_secret_deserialize_attr(prefix: &str, f: &Field<'_>) -> proc_macro2::TokenStream {
+        if prefix != "File" || !f.has_secret_deserialize() {
+            return proc_macro2::TokenStream::new();
+        }
+        let function = format!("deserialize_secret_{}", f.name());
+        // an absent field stays `None`, as without `deserialize_with`
+        let default = if f.opt {
+            quote! { default, }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        quote! { #[serde(#default deserialize_with = #function)] }
+    }
+
+    /// Output the field of the file or clap struct giving the file containing a secret value,
+    /// with its leading separator
+    fn output_secret_file_field(&self, prefix: &str, f: &Field<'_>) -> proc_macro2::TokenStream {
+        if !f.has_secret_file() {
+            return proc_macro2::TokenStream::new();
+        }
+        let ident = f.secret_file_ident();
+        match prefix {
+            "File" => {
+                let Some(key) = f.file_key() else {
+                    return proc_macro2::TokenStream::new();
+                };
+                let key = format!("{key}_file");
+                let skip = if self.attrs.contains_key("cli_settings_serialize") {
+                    quote! { , skip_serializing_if = "Option::is_none" }
+                } else {
+                    proc_macro2::TokenStream::new()
+                };
+                quote! {
+                    , #[serde(rename = #key #skip)] #ident: Option<std::path::PathBuf>
+                }
+            }
+            "Clap" => {
+                let Some((id, long)) = self.clap_id_long(f) else {
+                    return proc_macro2::TokenStream::new();
+                };
+                let doc = format!("File containing the value of --{long}");
+                let file_id = format!("{id}-file");
+                let file_long = format!("{long}-file");
+                quote! {
+                    , #[doc = #doc]
+                    #[arg(id = #file_id, long = #file_long, value_name = "PATH", conflicts_with = #id)]
+                    #ident: Option<std::path::PathBuf>
+                }
+            }
+            _ => proc_macro2::TokenStream::new(),
+        }
+    }
+
+    /// Identifier and long name of the command line argument of a field, if it has a long name
+    fn clap_id_long(&self, f: &Field<'_>) -> Option<(String, String)> {
+        let clap = f.attrs.get("cli_settings_clap")?;
+        let long = attr_key_value(clap.clone(), "long", &f.name().replace('_', "-"))?;
+        Some(match &self.clap_prefix {
+            Some(prefix) => {
+                let name = format!("{prefix}-{long}");
+                (name.clone(), name)
+            }
+            None => (f.name(), long),
+        })
+    }
+
+    /// Output the extra clap annotation of a field of a section, to prefix its long argument
+    fn output_clap_prefix(&self, f: &Field<'_>) -> proc_macro2::TokenStream {
+        if self.clap_prefix.is_none() {
+            return proc_macro2::TokenStream::new();
+        }
+        match self.clap_id_long(f) {
+            Some((name, _)) => quote! { #[arg(id = #name, long = #name)] },
+            None => proc_macro2::TokenStream::new(),
+        }
+    }
+
+    /// Output a section, nested in another settings struct:
+    /// no build, only the file and clap structs with their `update()`, exposed with type aliases
+    fn output_section(&self) -> proc_macro2::TokenStream {
+        let vis = &self.s.vis;
+        let ident = &self.s.ident;
+        let mod_name = format!("_cli_settings_derive_{}", to_snake_case(&ident.to_string()));
+        let mod_ident = syn::Ident::new(&mod_name, ident.span());
+        let file_ident = syn::Ident::new(&format!("File{ident}"), ident.span());
+        let file_alias = nested_alias_ident("File", ident);
+        let clap_ident = syn::Ident::new(&format!("Clap{ident}"), ident.span());
+        let clap_alias = nested_alias_ident("Clap", ident);
+
+        let main_struct = self.output_main_struct();
+        let main_struct_default = self.output_main_struct_default();
+        let file_struct = self.output_file_struct();
+        let file_struct_update = self.output_file_struct_update();
+        let section_from = self.output_section_from();
+        let section_keys = self.output_section_keys();
+        let clap_struct = self.output_clap_struct();
+        let clap_struct_update = self.output_clap_struct_update();
+        let section_diff = self.output_section_diff();
+        quote! {
+            #main_struct
+            #main_struct_default
+            #section_diff
+
+            #[doc(hidden)]
+            #vis type #file_alias = #mod_ident::#file_ident;
+            #[doc(hidden)]
+            #vis type #clap_alias = #mod_ident::#clap_ident;
+
+            mod #mod_ident {
+                use super::*;
+
+                #file_struct
+                #file_struct_update
+                #section_from
+                #section_keys
+
+                #clap_struct
+                #clap_struct_update
+            }
+        }
+    }
+
+    /// Output the main structure
+    fn output_main_struct(&self) -> proc_macro2::TokenStream {
+        let main_struct = self.output_struct("", None, &["_", "doc"]);
+        let debug = self.output_debug();
+        quote! {
+            #main_struct
+            #debug
+        }
+    }
+
+    /// Output `Debug` implementation for the main struct, masking the secret fields, if any
+    fn output_debug(&self) -> proc_macro2::TokenStream {
+        if !self.has_secrets() {
+            return proc_macro2::TokenStream::new();
+        }
+        let ident = &self.s.ident;
+        let name = ident.to_string();
+        let fields = self.fields.iter().map(|f| {
+            let field_ident = f.ident;
+            let field_name = f.name();
+            if f.secret {
+                quote! { .field(#field_name, &format_args!("***")) }
+            } else {
+                quote! { .field(#field_name, &self.#field_ident) }
+            }
+        });
+        quote! {
+            impl std::fmt::Debug for #ident {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    f.debug_struct(#name)
+                        #(#fields)*
+                        .finish()
+                }
+            }
+        }
+    }
+    /// Output the file structure, with the deserialization of its secret fields
+    fn output_file_struct(&self) -> proc_macro2::TokenStream {
+        let file_struct =
+            self.output_struct("File", Some("cli_settings_file"), &["cli_settings_file"]);
+        let deserialize_secrets = self
+            .fields
+            .iter()
+            .filter(|f| f.has_secret_deserialize())
+            .filter_map(|f| {
+                let function =
+                    syn::Ident::new(&format!("deserialize_secret_{}", f.name()), f.ident.span());
+                let message = format!("{SECRET_ERROR}{}'", f.file_key()?);
+                Some(quote! {
+                    /// Deserialize the value of a secret field, with an error that does not include the value
+                    fn #function<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+                    where
+                        D: serde::Deserializer<'de>,
+                        T: serde::Deserialize<'de>,
+                    {
+                        T::deserialize(deserializer).map_err(|_| serde::de::Error::custom(#message))
+                    }
+                })
+            });
+        quote! {
+            #file_struct
+            #(#deserialize_secrets)*
+        }
+    }
+    /// Output the clap structure
+    fn output_clap_struct(&self) -> proc_macro2::TokenStream {
+        self.output_struct(
+            "Clap",
+            Some("cli_settings_clap"),
+            &["doc", "cli_settings_clap"],
+        )
+    }
+
+    /// Output Default implementation for the main struct
+    fn output_main_struct_default(&self) -> proc_macro2::TokenStream {
+        let default = proc_macro2::TokenStream::from_str("Default::default()").unwrap();
+        let ident = &self.s.ident;
+        let fields = self
+            .fields
+            .iter()
+            .map(|f| {
+                let field_ident = f.ident;
+                let field_default = f.attrs.get("cli_settings_default").unwrap_or(&default);
+                // output one field (without separator)
+                quote! {
+                    #field_ident: #field_default
+                }
+            })
+            .collect::<Vec<_>>();
+        quote! {
+            impl Default for #ident {
+                fn default() -> Self {
+                    Self{
+                        #(#fields),*
+                    }
+                }
+            }
+        }
+    }
+
+    /// Output `build()` implementation for the main struct
+    fn output_main_struct_build(&self) -> proc_macro2::TokenStream {
+        let ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let sources_ident = self.suffixed_ident("Sources");
+        let load_env = if self.has_env() {
+            quote! { _cli_settings_derive::load_env(&mut cfg, &mut sources)?; }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let load = if self.config_arg.is_some() {
+            // the command line is parsed first, to get the configuration files
+            quote! {
+                _cli_settings_derive::load_with_config_arg(cfg_files, args, &mut cfg, &mut sources)?;
+            }
+        } else {
+            quote! {
+                for file in cfg_files {
+                    _cli_settings_derive::load_file(&file.into(), &mut cfg, &mut sources)?;
+                }
+                #load_env
+                _cli_settings_derive::parse_cli_args(args, &mut cfg, &mut sources)?;
+            }
+        };
+        let validate = if self.has_checks() {
+            quote! { _cli_settings_derive::validate(&cfg, &sources)?; }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let validate_with = self.attrs.get("cli_settings_validate_with").map(|check| {
+            quote! {
+                #check(&cfg).map_err(|err| #error_ident::InvalidSettings { source: err.into() })?;
+            }
+        });
+        let config_files_build = self.output_config_files_build(&quote! {
+            let mut cfg = Self::default();
+            let mut sources = #sources_ident::default();
+            #load
+            #validate
+            #validate_with
+            Ok((cfg, sources))
+        });
+        quote! {
+            impl #ident {
+                pub fn build<F, I, T>(cfg_files: F, args: I) -> Result<Self, #error_ident>
+                where
+                    F: IntoIterator<Item = std::path::PathBuf>,
+                    I: IntoIterator<Item = T>,
+                    T: Into<std::ffi::OsString> + Clone,
+                {
+                    Self::build_with_sources(cfg_files, args).map(|(cfg, _)| cfg)
+                }
+
+                /// Same as `build()`, but return the command line errors, including help and version
+                /// requests, instead of exiting the process
+                pub fn try_build<F, I, T>(cfg_files: F, args: I) -> Result<Self, #error_ident>
+                where
+                    F: IntoIterator<Item = std::path::PathBuf>,
+                    I: IntoIterator<Item = T>,
+                    T: Into<std::ffi::OsString> + Clone,
+                {
+                    Self::try_build_with_sources(cfg_files, args).map(|(cfg, _)| cfg)
+                }
+
+                /// Same as `build()`, also returning the source of each setting value
+                pub fn build_with_sources<F, I, T>(
+                    cfg_files: F,
+                    args: I,
+                ) -> Result<(Self, #sources_ident), #error_ident>
+                where
+                    F: IntoIterator<Item = std::path::PathBuf>,
+                    I: IntoIterator<Item = T>,
+                    T: Into<std::ffi::OsString> + Clone,
+                {
+                    match Self::try_build_with_sources(cfg_files, args) {
+                        Err(#error_ident::Help(err) | #error_ident::Cli(err)) => err.exit(),
+                        res => res,
+                    }
+                }
+
+                /// Same as `try_build()`, also returning the source of each setting value
+                pub fn try_build_with_sources<F, I, T>(
+                    cfg_files: F,
+                    args: I,
+                ) -> Result<(Self, #sources_ident), #error_ident>
+                where
+                    F: IntoIterator<Item = std::path::PathBuf>,
+                    I: IntoIterator<Item = T>,
+                    T: Into<std::ffi::OsString> + Clone,
+                {
+                    Self::try_build_with_config_files(cfg_files, args)
+                }
+            }
+
+            #config_files_build
+        }
+    }
+
+    /// Output the build methods of the main struct taking the configuration files as file specs, `body` building
+    /// the settings and their sources
+    fn output_config_files_build(
+        &self,
+        body: &proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let sources_ident = self.suffixed_ident("Sources");
+        let config_file_ident = self.suffixed_ident("ConfigFile");
+        let build_doc = format!(
+            " Same as `build()`, with the configuration files given as `{config_file_ident}`, some of them being\n \
+             possibly required, or as paths"
+        );
+        let try_build_doc = format!(
+            " Same as `try_build_with_sources()`, with the configuration files given as `{config_file_ident}`,\n \
+             some of them being possibly required, or as paths"
+        );
+        quote! {
+            impl #ident {
+                #[doc = #build_doc]
+                pub fn build_with_config_files<F, I, T>(cfg_files: F, args: I) -> Result<Self, #error_ident>
+                where
+                    F: IntoIterator,
+                    F::Item: Into<#config_file_ident>,
+                    I: IntoIterator<Item = T>,
+                    T: Into<std::ffi::OsString> + Clone,
+                {
+                    match Self::try_build_with_config_files(cfg_files, args) {
+                        Ok((cfg, _)) => Ok(cfg),
+                        Err(#error_ident::Help(err) | #error_ident::Cli(err)) => err.exit(),
+                        Err(err) => Err(err),
+                    }
+                }
+
+                #[doc = #try_build_doc]
+                pub fn try_build_with_config_files<F, I, T>(
+                    cfg_files: F,
+                    args: I,
+                ) -> Result<(Self, #sources_ident), #error_ident>
+                where
+                    F: IntoIterator,
+                    F::Item: Into<#config_file_ident>,
+                    I: IntoIterator<Item = T>,
+                    T: Into<std::ffi::OsString> + Clone,
+                {
+                    #body
+                }
+            }
+        }
+    }
+
+    /// Identifier derived from the struct name with the given suffix
+    fn suffixed_ident(&self, suffix: &str) -> syn::Ident {
+        let name = format!("{}{}", self.s.ident, suffix);
+        syn::Ident::new(&name, self.s.ident.span())
+    }
+
+    /// Output the enum of the configuration file formats
+    fn output_format(&self) -> proc_macro2::TokenStream {
+        let vis = &self.s.vis;
+        let format_ident = self.suffixed_ident("Format");
+        let formats = &self.formats;
+        let variants = formats.iter().map(|f| f.variant()).collect::<Vec<_>>();
+        let variants_doc = formats
+            .iter()
+            .map(|f| format!(" {} format", f.name().to_uppercase()))
+            .collect::<Vec<_>>();
+        let extensions = formats
+            .iter()
+            .map(|f| {
+                let variant = f.variant();
+                let extensions = f.extensions();
+                // output one match arm
+                quote! {
+                    Some(#(#extensions)|*) => Some(Self::#variant),
+                }
+            })
+            .collect::<Vec<_>>();
+        let default = if let Some(format) = self.format {
+            let variant = format.variant();
+            quote! { Some(Self::#variant) }
+        } else {
+            quote! { None }
+        };
+        quote! {
+            /// Configuration file format
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            #vis enum #format_ident {
+                #(#[doc = #variants_doc] #variants),*
+            }
+
+            impl #format_ident {
+                /// Get the format of a configuration file from its extension,
+                /// or the default format for unknown extensions
+                pub fn from_path(path: &std::path::Path) -> Option<Self> {
+                    match path.extension().and_then(std::ffi::OsStr::to_str) {
+                        #(#extensions)*
+                        _ => #default,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Output the configuration file discovery methods of the main struct, if requested
+    fn output_discovery(&self) -> proc_macro2::TokenStream {
+        let Some(app) = &self.app else {
+            return proc_macro2::TokenStream::new();
+        };
+        let ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let extensions = self
+            .formats
+            .iter()
+            .flat_map(|f| f.extensions())
+            .collect::<Vec<_>>();
+        let system_dir = format!("/etc/{app}");
+        let local_name = format!(".{app}");
+        quote! {
+            impl #ident {
+                /// Configuration files of the application, from the lowest to the highest precedence:
+                /// `/etc/<app>/`, `$XDG_CONFIG_DIRS/<app>/`, `$XDG_CONFIG_HOME/<app>/` (`config.<ext>` files),
+                /// then `./.<app>.<ext>`
+                pub fn default_config_paths() -> Vec<std::path::PathBuf> {
+                    const EXTENSIONS: &[&str] = &[#(#extensions),*];
+                    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty());
+                    let mut dirs = vec![std::path::PathBuf::from(#system_dir)];
+                    // XDG_CONFIG_DIRS is ordered by decreasing precedence
+                    let config_dirs = var("XDG_CONFIG_DIRS").unwrap_or_else(|| "/etc/xdg".into());
+                    let mut config_dirs = std::env::split_paths(&config_dirs).collect::<Vec<_>>();
+                    config_dirs.reverse();
+                    dirs.extend(config_dirs.into_iter().map(|dir| dir.join(#app)));
+                    let config_home = var("XDG_CONFIG_HOME")
+                        .map(std::path::PathBuf::from)
+                        .or_else(|| var("HOME").map(|home| std::path::Path::new(&home).join(".config")));
+                    dirs.extend(config_home.map(|dir| dir.join(#app)));
+                    let mut paths = Vec::new();
+                    for dir in dirs {
+                        paths.extend(EXTENSIONS.iter().map(|ext| dir.join(format!("config.{ext}"))));
+                    }
+                    paths.extend(
+                        EXTENSIONS
+                            .iter()
+                            .map(|ext| std::path::PathBuf::from(format!("{}.{ext}", #local_name))),
+                    );
+                    paths
+                }
+
+                /// Same as `build()`, with the configuration files of `default_config_paths()`
+                pub fn build_discovered<I, T>(args: I) -> Result<Self, #error_ident>
+                where
+                    I: IntoIterator<Item = T>,
+                    T: Into<std::ffi::OsString> + Clone,
+                {
+                    Self::build(Self::default_config_paths(), args)
+                }
+
+                /// Same as `try_build()`, with the configuration files of `default_config_paths()`
+                pub fn try_build_discovered<I, T>(args: I) -> Result<Self, #error_ident>
+                where
+                    I: IntoIterator<Item = T>,
+                    T: Into<std::ffi::OsString> + Clone,
+                {
+                    Self::try_build(Self::default_config_paths(), args)
+                }
+            }
+        }
+    }
+
+    /// Output the watch of the configuration files, if requested
+    fn output_watch(&self) -> proc_macro2::TokenStream {
+        let Some(period) = self.watch else {
+            return proc_macro2::TokenStream::new();
+        };
+        let vis = &self.s.vis;
+        let ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let config_file_ident = self.suffixed_ident("ConfigFile");
+        let watcher_ident = self.suffixed_ident("Watcher");
+        let doc = format!(
+            " Handle of the watch of the configuration files, returned by `{ident}::watch()`;\n \
+             the watch stops when all the handles are dropped"
+        );
+        let (sighup_register, sighup_pending, sighup_unregister) = if self.sighup {
+            (
+                quote! {
+                    // SIGHUP requests a build, like `reload()`
+                    let sighup = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+                    #[cfg(unix)]
+                    let sighup_id =
+                        signal_hook::flag::register(signal_hook::consts::SIGHUP, std::sync::Arc::clone(&sighup))
+                            .map_err(|source| #error_ident::Signal { source })?;
+                },
+                quote! { sighup.swap(false, std::sync::atomic::Ordering::Relaxed) },
+                quote! {
+                    #[cfg(unix)]
+                    signal_hook::low_level::unregister(sighup_id);
+                },
+            )
+        } else {
+            (
+                proc_macro2::TokenStream::new(),
+                quote! { false },
+                proc_macro2::TokenStream::new(),
+            )
+        };
+        quote! {
+            impl #ident {
+                /// Same as `try_build()`, then watch the configuration files: the settings are built again
+                /// when a file changes or when requested by `reload()` on the returned handle, and `callback`
+                /// gets the previous and the new settings, or the error keeping the previous settings
+                pub fn watch<F, I, T, C>(
+                    cfg_files: F,
+                    args: I,
+                    mut callback: C,
+                ) -> Result<(Self, #watcher_ident), #error_ident>
+                where
+                    Self: Clone + Send + 'static,
+                    F: IntoIterator,
+                    F::Item: Into<#config_file_ident>,
+                    I: IntoIterator<Item = T>,
+                    T: Into<std::ffi::OsString> + Clone,
+                    C: FnMut(Result<(&Self, &Self), #error_ident>) + Send + 'static,
+                {
+                    let files = cfg_files.into_iter().map(Into::into).collect::<Vec<_>>();
+                    let args = args.into_iter().map(Into::into).collect::<Vec<std::ffi::OsString>>();
+                    // the files loaded by the build are stamped as they are read, with the `--config` and included files
+                    let (cfg, sources) = Self::try_build_with_config_files(files.clone(), args.clone())?;
+                    let mut stamp = sources.files;
+                    let mut current = cfg.clone();
+                    #sighup_register
+                    let (sender, receiver) = std::sync::mpsc::channel();
+                    std::thread::spawn(move || {
+                        loop {
+                            let reload = match receiver.recv_timeout(std::time::Duration::from_millis(#period)) {
+                                Ok(()) => true,
+                                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => #sighup_pending,
+                                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+                            };
+                            // stamp before the build, kept if the build fails, to detect the next change of the files
+                            let new_stamp = stamp
+                                .iter()
+                                .map(|(path, _)| (path.clone(), _cli_settings_derive::file_stamp(path)))
+                                .collect::<Vec<_>>();
+                            if !reload && new_stamp == stamp {
+                                continue;
+                            }
+                            match Self::try_build_with_config_files(files.clone(), args.clone()) {
+                                Ok((cfg, sources)) => {
+                                    stamp = sources.files;
+                                    callback(Ok((&current, &cfg)));
+                                    current = cfg;
+                                }
+                                Err(err) => {
+                                    stamp = new_stamp;
+                                    callback(Err(err));
+                                }
+                            }
+                        }
+                        #sighup_unregister
+                    });
+                    Ok((cfg, #watcher_ident { sender }))
+                }
+            }
+
+            #[doc = #doc]
+            #[derive(Debug, Clone)]
+            #vis struct #watcher_ident {
+                sender: std::sync::mpsc::Sender<()>,
+            }
+
+            impl #watcher_ident {
+                /// Build the settings again, for example on a request of the application
+                pub fn reload(&self) {
+                    // the watch is stopped only when all the handles are dropped
+                    let _ = self.sender.send(());
+                }
+            }
+        }
+    }
+
+    /// Output `file_stamp()` function, getting the modification time and length of a watched file, if requested
+    fn output_file_stamp(&self) -> proc_macro2::TokenStream {
+        if self.watch.is_none() {
+            return proc_macro2::TokenStream::new();
+        }
+        quote! {
+            pub fn file_stamp(path: &std::path::Path) -> Option<(std::time::SystemTime, u64)> {
+                // the length as well, as the modification time may be coarse
+                let metadata = std::fs::metadata(path).ok()?;
+                Some((metadata.modified().ok()?, metadata.len()))
+            }
+        }
+    }
+
+    /// Output the serialization methods of the main struct, if requested
+    fn output_serialize(&self) -> proc_macro2::TokenStream {
+        if !self.attrs.contains_key("cli_settings_serialize") {
+            return proc_macro2::TokenStream::new();
+        }
+        let ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let format_ident = self.suffixed_ident("Format");
+        quote! {
+            impl #ident {
+                /// Serialize the settings that can be set from a configuration file, in the given format
+                pub fn to_file_string(&self, format: #format_ident) -> Result<String, #error_ident> {
+                    _cli_settings_derive::to_file_string(self, format)
+                }
+
+                /// Generate a sample configuration file in the given format, with the default value of each
+                /// setting that can be set from a configuration file, documented by its doc comment
+                pub fn sample_config(format: #format_ident) -> Result<String, #error_ident> {
+                    _cli_settings_derive::sample_config(format)
+                }
+
+                /// Write the settings that can be set from a configuration file, in the format
+                /// selected from the file extension
+                pub fn write_config(&self, path: &std::path::Path) -> Result<(), #error_ident> {
+                    let format = #format_ident::from_path(path).ok_or_else(|| {
+                        #error_ident::FileFormat {
+                            path: path.to_path_buf(),
+                        }
+                    })?;
+                    std::fs::write(path, self.to_file_string(format)?).map_err(|err| {
+                        #error_ident::FileWrite {
+                            path: path.to_path_buf(),
+                            source: err,
+                        }
+                    })
+                }
+            }
+        }
+    }
+
+    /// Output `to_file_string()` function, if requested
+    fn output_to_file_string(&self) -> proc_macro2::TokenStream {
+        if !self.attrs.contains_key("cli_settings_serialize") {
+            return proc_macro2::TokenStream::new();
+        }
+        let main_ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let format_ident = self.suffixed_ident("Format");
+        let name = format!("File{}", self.s.ident);
+        let file_ident = syn::Ident::new(&name, self.s.ident.span());
+        let all_fields = self.output_file_config(None);
+        let sample_config = self.output_sample_config();
+        let formats = self
+            .formats
+            .iter()
+            .map(|f| {
+                let variant = f.variant();
+                let serialize = f.output_serialize();
+                // output one match arm
+                quote! {
+                    super::#format_ident::#variant => #serialize,
+                }
+            })
+            .collect::<Vec<_>>();
+        let escape = if self.interpolate {
+            quote! {
+                // `$` written as `$$` in the string values, not to be taken for a reference when loaded
+                let text = match format {
+                    #(#formats)*
+                }?;
+                let value: Value = parse_format(std::path::Path::new(""), &text, format)
+                    .map_err(serialize_error)?;
+                let file_config = &escape(value);
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        quote! {
+            pub fn to_file_string(
+                cfg: &super::#main_ident,
+                format: super::#format_ident,
+            ) -> Result<String, super::#error_ident> {
+                serialize(&#all_fields, format)
+            }
+
+            #sample_config
+
+            fn serialize(
+                file_config: &#file_ident,
+                format: super::#format_ident,
+            ) -> Result<String, super::#error_ident> {
+                #escape
+                match format {
+                    #(#formats)*
+                }
+            }
+
+            fn serialize_error<E>(err: E) -> super::#error_ident
+            where
+                E: std::error::Error + Send + Sync + 'static,
+            {
+                super::#error_ident::Serialize {
+                    source: Box::new(err),
+                }
+            }
+        }
+    }
+
+    /// Output the file struct built from the settings `cfg`, with all fields or only the selected one
+    fn output_file_config(&self, selected: Option<&syn::Ident>) -> proc_macro2::TokenStream {
+        let name = format!("File{}", self.s.ident);
+        let file_ident = syn::Ident::new(&name, self.s.ident.span());
+        let fields = self
+            .fields
+            .iter()
+            .filter(|f| f.attrs.contains_key("cli_settings_file"))
+            .map(|f| {
+                let field_ident = f.ident;
+                let value = if f.secret {
+                    // secret values are never output
+                    return if f.has_secret_file() {
+                        let secret_file_ident = f.secret_file_ident();
+                        quote! { #field_ident: None, #secret_file_ident: None }
+                    } else if f.opt {
+                        quote! { #field_ident: None }
+                    } else {
+                        quote! { #field_ident: Default::default() }
+                    };
+                } else if f.nested {
+                    quote! { From::from(&cfg.#field_ident) }
+                } else if f.merge == Merge::Deep {
+                    quote! {
+                        cfg.#field_ident
+                            .iter()
+                            .map(|(key, item)| (key.clone(), Some(item.clone())))
+                            .collect()
+                    }
+                } else {
+                    quote! { cfg.#field_ident.clone() }
+                };
+                // output one field (without separator)
+                if !f.opt {
+                    quote! { #field_ident: #value }
+                } else if selected.is_none_or(|ident| ident == field_ident) {
+                    quote! { #field_ident: Some(#value) }
+                } else {
+                    quote! { #field_ident: None }
+                }
+            });
+        quote! {
+            #file_ident {
+                #(#fields),*
+            }
+        }
+    }
+
+    /// Output the comparison of the main struct, if requested
+    fn output_diff(&self) -> proc_macro2::TokenStream {
+        if !self.attrs.contains_key("cli_settings_diff") {
+            return proc_macro2::TokenStream::new();
+        }
+        let vis = &self.s.vis;
+        let ident = &self.s.ident;
+        let field_change_ident = self.suffixed_ident("FieldChange");
+        let doc = format!(" Change of a setting, returned by `{ident}::diff()`");
+        quote! {
+            impl #ident {
+                /// List the settings whose value differs in `other`, with their values formatted with `Debug`
+                pub fn diff(&self, other: &Self) -> Vec<#field_change_ident> {
+                    let mut changes = Vec::new();
+                    _cli_settings_derive::diff(self, other, "", &mut changes);
+                    changes
+                        .into_iter()
+                        .map(|(field, old, new)| #field_change_ident { field, old, new })
+                        .collect()
+                }
+            }
+
+            #[doc = #doc]
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            #vis struct #field_change_ident {
+                /// Name of the field, after the names of the enclosing nested fields (`database.host`)
+                pub field: String,
+                /// Value in the settings compared
+                pub old: String,
+                /// Value in the other settings
+                pub new: String,
+            }
+        }
+    }
+
+    /// Output the comparison of the fields of `old` and `new`, pushed to `changes`
+    fn output_diff_fields(&self) -> proc_macro2::TokenStream {
+        let fields = self.fields.iter().map(|f| {
+            let field_ident = f.ident;
+            let name = f.name();
+            if f.nested {
+                quote! {
+                    old.#field_ident.cli_settings_diff(&new.#field_ident, &format!("{prefix}{}.", #name), changes);
+                }
+            } else {
+                let (old_value, new_value) = if f.secret {
+                    (quote! { "***".to_string() }, quote! { "***".to_string() })
+                } else {
+                    (
+                        quote! { format!("{:?}", old.#field_ident) },
+                        quote! { format!("{:?}", new.#field_ident) },
+                    )
+                };
+                quote! {
+                    if old.#field_ident != new.#field_ident {
+                        changes.push((format!("{prefix}{}", #name), #old_value, #new_value));
+                    }
+                }
+            }
+        });
+        quote! { #(#fields)* }
+    }
+
+    /// Output `diff()` function, if requested
+    fn output_diff_function(&self) -> proc_macro2::TokenStream {
+        if !self.attrs.contains_key("cli_settings_diff") {
+            return proc_macro2::TokenStream::new();
+        }
+        let main_ident = &self.s.ident;
+        let diff_fields = self.output_diff_fields();
+        quote! {
+            pub fn diff(
+                old: &super::#main_ident,
+                new: &super::#main_ident,
+                prefix: &str,
+                changes: &mut Vec<(String, String, String)>,
+            ) {
+                #diff_fields
+            }
+        }
+    }
+
+    /// Output the comparison of a section, called by the comparison of the enclosing struct, if requested
+    fn output_section_diff(&self) -> proc_macro2::TokenStream {
+        if !self.attrs.contains_key("cli_settings_diff") {
+            return proc_macro2::TokenStream::new();
+        }
+        let ident = &self.s.ident;
+        let diff_fields = self.output_diff_fields();
+        quote! {
+            impl #ident {
+                #[doc(hidden)]
+                pub fn cli_settings_diff(
+                    &self,
+                    other: &Self,
+                    prefix: &str,
+                    changes: &mut Vec<(String, String, String)>,
+                ) {
+                    let (old, new) = (self, other);
+                    #diff_fields
+                }
+            }
+        }
+    }
+
+    /// Output `From` implementation converting the settings of a section to its file struct, if requested
+    fn output_section_from(&self) -> proc_macro2::TokenStream {
+        if !self.attrs.contains_key("cli_settings_serialize") {
+            return proc_macro2::TokenStream::new();
+        }
+        let main_ident = &self.s.ident;
+        let name = format!("File{}", self.s.ident);
+        let file_ident = syn::Ident::new(&name, self.s.ident.span());
+        let all_fields = self.output_file_config(None);
+        quote! {
+            impl From<&super::#main_ident> for #file_ident {
+                fn from(cfg: &super::#main_ident) -> Self {
+                    #all_fields
+                }
+            }
+        }
+    }
+
+    /// Output `sample_config()` function
+    fn output_sample_config(&self) -> proc_macro2::TokenStream {
+        let main_ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let format_ident = self.suffixed_ident("Format");
+        let file_fields = self
+            .fields
+            .iter()
+            .filter(|f| f.attrs.contains_key("cli_settings_file") && !f.secret);
+        let entries = file_fields
+            .map(|f| {
+                let doc = doc_lines(&f.attrs);
+                let file_config = self.output_file_config(Some(f.ident));
+                // output one entry (without separator)
+                quote! {
+                    (&[#(#doc),*], serialize(&#file_config, format)?)
+                }
+            })
+            .collect::<Vec<_>>();
+        let comments = self
+            .formats
+            .iter()
+            .map(|f| {
+                let variant = f.variant();
+                let arm = match (f, f.comment()) {
+                    (_, None) => quote! { return to_file_string(&cfg, format) },
+                    (Format::Json5, Some(comment)) => quote! { (#comment, "  ", "{\n", "}\n") },
+                    (_, Some(comment)) => quote! { (#comment, "", "", "") },
+                };
+                // output one match arm
+                quote! {
+                    super::#format_ident::#variant => #arm,
+                }
+            })
+            .collect::<Vec<_>>();
+        let toml_sort = if self.formats.contains(&Format::Toml) {
+            quote! {
+                if format == super::#format_ident::Toml {
+                    // TOML tables shall be after the plain values
+                    entries.sort_by_key(|(_, text)| text.lines().any(|l| l.starts_with('[')));
+                }
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let json5_entry = if self.formats.contains(&Format::Json5) {
+            quote! {
+                let text = if format == super::#format_ident::Json5 {
+                    // keep the member only, without the enclosing braces
+                    let text = text.trim();
+                    let member = text
+                        .strip_prefix('{')
+                        .and_then(|t| t.strip_suffix('}'))
+                        .unwrap_or(text);
+                    format!("{member},\n")
+                } else {
+                    text
+                };
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        quote! {
+            #[allow(unreachable_code)]
+            pub fn sample_config(format: super::#format_ident) -> Result<String, super::#error_ident> {
+                let cfg = super::#main_ident::default();
+                let (comment, indent, start, end): (&str, &str, &str, &str) = match format {
+                    #(#comments)*
+                };
+                #[allow(unused_mut)]
+                let mut entries: Vec<(&[&str], String)> = vec![#(#entries),*];
+                #toml_sort
+                let mut res = start.to_string();
+                for (i, (doc, text)) in entries.into_iter().enumerate() {
+                    if i > 0 && indent.is_empty() {
+                        res.push('\n');
+                    }
+                    for line in doc {
+                        res.push_str(&format!("{indent}{comment}{line}\n"));
+                    }
+                    #json5_entry
+                    res.push_str(indent);
+                    res.push_str(&text);
+                }
+                res.push_str(end);
+                Ok(res)
+            }
+        }
+    }
+
+    /// Output the type describing a configuration file to load
+    fn output_config_file(&self) -> proc_macro2::TokenStream {
+        let vis = &self.s.vis;
+        let ident = &self.s.ident;
+        let config_file_ident = self.suffixed_ident("ConfigFile");
+        let doc =
+            format!(" Configuration file to load, passed to `{ident}::build_with_config_files()`");
+        quote! {
+            #[doc = #doc]
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            #vis struct #config_file_ident {
+                path: std::path::PathBuf,
+                required: bool,
+            }
+
+            impl #config_file_ident {
+                /// Optional configuration file, skipped if it does not exist
+                pub fn optional(path: impl Into<std::path::PathBuf>) -> Self {
+                    Self {
+                        path: path.into(),
+                        required: false,
+                    }
+                }
+
+                /// Required configuration file, its absence being an error
+                pub fn required(path: impl Into<std::path::PathBuf>) -> Self {
+                    Self {
+                        path: path.into(),
+                        required: true,
+                    }
+                }
+
+                /// Path of the configuration file
+                pub fn path(&self) -> &std::path::Path {
+                    &self.path
+                }
+
+                /// Whether the configuration file is required
+                pub fn is_required(&self) -> bool {
+                    self.required
+                }
+            }
+
+            impl From<std::path::PathBuf> for #config_file_ident {
+                fn from(path: std::path::PathBuf) -> Self {
+                    Self::optional(path)
+                }
+            }
+
+            impl From<&std::path::Path> for #config_file_ident {
+                fn from(path: &std::path::Path) -> Self {
+                    Self::optional(path)
+                }
+            }
+
+            impl From<&std::path::PathBuf> for #config_file_ident {
+                fn from(path: &std::path::PathBuf) -> Self {
+                    Self::optional(path)
+                }
+            }
+
+            impl From<String> for #config_file_ident {
+                fn from(path: String) -> Self {
+                    Self::optional(path)
+                }
+            }
+
+            impl From<&str> for #config_file_ident {
+                fn from(path: &str) -> Self {
+                    Self::optional(path)
+                }
+            }
+        }
+    }
+
+    /// Output the types reporting the source of each setting value
+    fn output_sources(&self) -> proc_macro2::TokenStream {
+        let vis = &self.s.vis;
+        let ident = &self.s.ident;
+        let sources_ident = self.suffixed_ident("Sources");
+        let source_ident = self.suffixed_ident("Source");
+        let doc =
+            format!(" Source of each setting value, returned by `{ident}::build_with_sources()`");
+        let field_names = self.fields.iter().map(Field::name).collect::<Vec<_>>();
+        // configuration files loaded or looked up, with their stamp when loaded, to watch them
+        let (files_field, files_default) = if self.watch.is_some() {
+            (
+                quote! { files: Vec<(std::path::PathBuf, Option<(std::time::SystemTime, u64)>)>, },
+                quote! { files: Vec::new(), },
+            )
+        } else {
+            Default::default()
+        };
+        quote! {
+            /// Source of a setting value
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            #vis enum #source_ident {
+                /// Default value
+                Default,
+                /// Configuration file
+                File(std::path::PathBuf),
+                /// Environment variable
+                Env(String),
+                /// Command line argument
+                Cli,
+            }
+
+            impl std::fmt::Display for #source_ident {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    match self {
+                        Self::Default => write!(f, "default value"),
+                        Self::File(path) => write!(f, "configuration file '{}'", path.display()),
+                        Self::Env(name) => write!(f, "environment variable '{name}'"),
+                        Self::Cli => write!(f, "command line"),
+                    }
+                }
+            }
+
+            #[doc = #doc]
+            #[derive(Debug, Clone, PartialEq, Eq)]
+            #vis struct #sources_ident {
+                fields: Vec<(&'static str, #source_ident)>,
+                unknown_keys: Vec<(std::path::PathBuf, String)>,
+                #files_field
+            }
+
+            impl Default for #sources_ident {
+                fn default() -> Self {
+                    Self {
+                        fields: vec![#((#field_names, #source_ident::Default)),*],
+                        unknown_keys: Vec::new(),
+                        #files_default
+                    }
+                }
+            }
+
+            impl #sources_ident {
+                /// Get the source of the value of the given field
+                pub fn get(&self, field: &str) -> Option<&#source_ident> {
+                    self.fields.iter().find(|(name, _)| *name == field).map(|(_, source)| source)
+                }
+
+                /// Iterate over the fields and the source of their value, in declaration order
+                pub fn iter(&self) -> impl Iterator<Item = (&'static str, &#source_ident)> {
+                    self.fields.iter().map(|(name, source)| (*name, source))
+                }
+
+                /// Keys of the configuration files matching no setting, with their file,
+                /// reported with `cli_settings_strict = "warn"`
+                pub fn unknown_keys(&self) -> &[(std::path::PathBuf, String)] {
+                    &self.unknown_keys
+                }
+
+                fn set(&mut self, field: &str, source: #source_ident) {
+                    if let Some(entry) = self.fields.iter_mut().find(|(name, _)| *name == field) {
+                        entry.1 = source;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Identifier of the error type
+    fn error_ident(&self) -> syn::Ident {
+        self.suffixed_ident("Error")
+    }
+
+    /// Output the error type returned by `build()`
+    fn output_error(&self) -> proc_macro2::TokenStream {
+        let vis = &self.s.vis;
+        let ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let source_ident = self.suffixed_ident("Source");
+        let doc = format!(" Error returned by `{ident}::build()` and `{ident}::try_build()`");
+        let display = self.output_error_display();
+        quote! {
+            #[doc = #doc]
+            #[derive(Debug)]
+            #[non_exhaustive]
+            #vis enum #error_ident {
+                /// Help or version message requested on the command line
+                Help(clap::Error),
+                /// Invalid command line arguments
+                Cli(clap::Error),
+                /// Failure to open a configuration file
+                FileOpen {
+                    /// path of the configuration file
+                    path: std::path::PathBuf,
+                    /// underlying error
+                    source: std::io::Error,
+                },
+                /// Configuration file with an unsupported extension
+                FileFormat {
+                    /// path of the configuration file
+                    path: std::path::PathBuf,
+                },
+                /// Failure to parse a configuration file
+                FileParse {
+                    /// path of the configuration file
+                    path: std::path::PathBuf,
+                    /// line of the error (1-based), if provided by the format
+                    line: Option<usize>,
+                    /// column of the error (1-based), if provided by the format
+                    column: Option<usize>,
+                    /// underlying error
+                    source: Box<dyn std::error::Error + Send + Sync>,
+                },
+                /// Configuration file including itself, directly or not, with `cli_settings_include`
+                IncludeCycle {
+                    /// path of the configuration file
+                    path: std::path::PathBuf,
+                },
+                /// Reference of a configuration file that cannot be expanded, with `cli_settings_interpolate`
+                Interpolation {
+                    /// path of the configuration file
+                    path: std::path::PathBuf,
+                    /// referenced name
+                    name: String,
+                    /// reason of the failure
+                    source: Box<dyn std::error::Error + Send + Sync>,
+                },
+                /// Key of a configuration file matching no setting, with `cli_settings_strict`
+                UnknownKey {
+                    /// path of the configuration file
+                    path: std::path::PathBuf,
+                    /// unknown key
+                    key: String,
+                    /// closest known key, if any
+                    suggestion: Option<String>,
+                },
+                /// Setting value rejected by a check of `cli_settings_validate`
+                Validation {
+                    /// name of the setting
+                    field: String,
+                    /// source of the rejected value
+                    origin: #source_ident,
+                    /// reason of the rejection
+                    source: Box<dyn std::error::Error + Send + Sync>,
+                },
+                /// Settings rejected by the method of `cli_settings_validate_with`
+                InvalidSettings {
+                    /// reason of the rejection
+                    source: Box<dyn std::error::Error + Send + Sync>,
+                },
+                /// Failure to serialize the settings
+                Serialize {
+                    /// underlying error
+                    source: Box<dyn std::error::Error + Send + Sync>,
+                },
+                /// Failure to write a configuration file
+                FileWrite {
+                    /// path of the configuration file
+                    path: std::path::PathBuf,
+                    /// underlying error
+                    source: std::io::Error,
+                },
+                /// Failure to register the SIGHUP handler of the watch, with `cli_settings_watch_sighup`
+                Signal {
+                    /// underlying error
+                    source: std::io::Error,
+                },
+                /// Failure to parse an environment variable
+                Env {
+                    /// name of the environment variable
+                    name: String,
+                    /// underlying error
+                    source: Box<dyn std::error::Error + Send + Sync>,
+                },
+                /// Failure to read or parse the file containing the value of a secret field
+                SecretFile {
+                    /// path of the secret file
+                    path: std::path::PathBuf,
+                    /// underlying error
+                    source: Box<dyn std::error::Error + Send + Sync>,
+                },
+            }
+
+            #display
+
+            impl std::error::Error for #error_ident {
+                fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                    match self {
+                        Self::Help(err) | Self::Cli(err) => err.source(),
+                        Self::FileOpen { source, .. }
+                        | Self::FileWrite { source, .. }
+                        | Self::Signal { source } => Some(source),
+                        Self::FileFormat { .. }
+                        | Self::IncludeCycle { .. }
+                        | Self::UnknownKey { .. } => None,
+                        Self::FileParse { source, .. }
+                        | Self::Validation { source, .. }
+                        | Self::InvalidSettings { source, .. }
+                        | Self::Serialize { source, .. }
+                        | Self::Env { source, .. }
+                        | Self::SecretFile { source, .. }
+                        | Self::Interpolation { source, .. } => Some(source.as_ref()),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Output `Display` implementation for the error enum
+    fn output_error_display(&self) -> proc_macro2::TokenStream {
+        let error_ident = self.error_ident();
+        quote! {
+            impl std::fmt::Display for #error_ident {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    match self {
+                        Self::Help(err) | Self::Cli(err) => err.fmt(f),
+                        Self::FileOpen { path, .. } => write!(
+                            f,
+                            "Failed to open the configuration file '{}'",
+                            path.display()
+                        ),
+                        Self::FileFormat { path } => write!(
+                            f,
+                            "Unsupported format of the configuration file '{}'",
+                            path.display()
+                        ),
+                        Self::FileParse { path, line, column, .. } => {
+                            write!(
+                                f,
+                                "Failed to parse the configuration file '{}'",
+                                path.display()
+                            )?;
+                            match (line, column) {
+                                (Some(line), Some(column)) => {
+                                    write!(f, " at line {line}, column {column}")
+                                }
+                                (Some(line), None) => write!(f, " at line {line}"),
+                                _ => Ok(()),
+                            }
+                        }
+                        Self::IncludeCycle { path } => write!(
+                            f,
+                            "Include cycle on the configuration file '{}'",
+                            path.display()
+                        ),
+                        Self::Interpolation { path, name, .. } => write!(
+                            f,
+                            "Failed to expand '${{{name}}}' in the configuration file '{}'",
+                            path.display()
+                        ),
+                        Self::UnknownKey { path, key, suggestion } => {
+                            write!(
+                                f,
+                                "Unknown key '{key}' in the configuration file '{}'",
+                                path.display()
+                            )?;
+                            match suggestion {
+                                Some(suggestion) => write!(f, ", did you mean '{suggestion}'?"),
+                                None => Ok(()),
+                            }
+                        }
+                        Self::Validation { field, origin, .. } => {
+                            write!(f, "Invalid value of the setting '{field}' from the {origin}")
+                        }
+                        Self::InvalidSettings { .. } => write!(f, "Invalid settings"),
+                        Self::Serialize { .. } => write!(f, "Failed to serialize the settings"),
+                        Self::FileWrite { path, .. } => write!(
+                            f,
+                            "Failed to write the configuration file '{}'",
+                            path.display()
+                        ),
+                        Self::Signal { .. } => write!(f, "Failed to register the SIGHUP handler"),
+                        Self::Env { name, .. } => {
+                            write!(f, "Failed to parse the environment variable '{name}'")
+                        }
+                        Self::SecretFile { path, .. } => {
+                            write!(f, "Failed to read the secret file '{}'", path.display())
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Output `update()` implementation for the file or clap struct
+    fn output_struct_update(&self, prefix: &str, field_filter: &str) -> proc_macro2::TokenStream {
+        let main_ident = &self.s.ident;
+        let sources_ident = self.suffixed_ident("Sources");
+        let source_ident = self.suffixed_ident("Source");
+        let name = format!("{}{}", prefix, self.s.ident);
+        let ident = syn::Ident::new(&name, self.s.ident.span());
+        let fields = self
+            .fields
+            .iter()
+            .filter(|f| f.attrs.contains_key(field_filter))
+            .map(|f| {
+                let field_ident = f.ident;
+                let field_name = f.name();
+                // record the update of a field
+                let set = if self.section {
+                    quote! { updated = true; }
+                } else {
+                    quote! { sources.set(#field_name, source.clone()); }
+                };
+                // output one field (without separator)
+                if f.nested && prefix == "Clap" {
+                    quote! {
+                        if self.#field_ident.update(&mut cfg.#field_ident) {
+                            #set
+                        }
+                    }
+                } else if f.nested {
+                    quote! {
+                        if let Some(param) = self.#field_ident {
+                            if param.update(&mut cfg.#field_ident) {
+                                #set
+                            }
+                        }
+                    }
+                } else {
+                    // deep merge of a map read from a file, where a null value removes the key
+                    let merge = |value| {
+                        if f.merge == Merge::Deep && prefix == "File" {
+                            f.output_deep_merge(&value)
+                        } else {
+                            f.output_merge(&value)
+                        }
+                    };
+                    if f.opt {
+                        let merge = merge(quote! { param });
+                        quote! {
+                            if let Some(param) = self.#field_ident {
+                                #merge
+                                #set
+                            }
+                        }
+                    } else {
+                        let merge = merge(quote! { self.#field_ident });
+                        quote! {
+                            #merge
+                            #set
+                        }
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+        if self.section {
+            return quote! {
+                impl #ident {
+                    /// Update the settings with the provided values, return whether any value was provided
+                    #[allow(unused_mut)]
+                    pub fn update(self, cfg: &mut super::#main_ident) -> bool {
+                        let mut updated = false;
+                        #(#fields)*
+                        updated
+                    }
+                }
+            };
+        }
+        quote! {
+            impl #ident {
+                fn update(
+                    self,
+                    cfg: &mut super::#main_ident,
+                    sources: &mut super::#sources_ident,
+                    source: &super::#source_ident,
+                ) {
+                    #(#fields)*
+                }
+            }
+        }
+    }
+    /// Output the file struct `update()`
+    fn output_file_struct_update(&self) -> proc_macro2::TokenStream {
+        let update = self.output_struct_update("File", "cli_settings_file");
+        let read_secrets = self.output_read_secrets("File", "cli_settings_file");
+        quote! {
+            #update
+            #read_secrets
+        }
+    }
+    /// Output the clap struct `update()`
+    fn output_clap_struct_update(&self) -> proc_macro2::TokenStream {
+        let update = self.output_struct_update("Clap", "cli_settings_clap");
+        let read_secrets = self.output_read_secrets("Clap", "cli_settings_clap");
+        quote! {
+            #update
+            #read_secrets
+        }
+    }
+
+    /// Output the reading of the secret values given as files, for the fields of the file or clap struct
+    fn output_read_secrets_fields(
+        &self,
+        prefix: &str,
+        field_filter: &str,
+    ) -> Vec<proc_macro2::TokenStream> {
+        self.fields
+            .iter()
+            .filter(|f| f.attrs.contains_key(field_filter))
+            .filter_map(|f| {
+                let field_ident = f.ident;
+                if f.nested && prefix == "Clap" {
+                    return Some(quote! { self.#field_ident.read_secrets()?; });
+                }
+                if f.nested {
+                    return Some(quote! {
+                        if let Some(section) = &mut self.#field_ident {
+                            section.read_secrets()?;
+                        }
+                    });
+                }
+                let given = match prefix {
+                    "File" => f.file_key().is_some(),
+                    _ => self.clap_id_long(f).is_some(),
+                };
+                if !f.has_secret_file() || !given {
+                    return None;
+                }
+                let secret_file_ident = f.secret_file_ident();
+                let read_secret = f.output_read_secret();
+                // the value and its file in the same configuration file (clap rejects them itself)
+                let conflict = match f.file_key() {
+                    Some(key) if prefix == "File" => {
+                        let reason = format!("conflicting with the key '{key}'");
+                        quote! {
+                            if self.#field_ident.is_some() {
+                                return Err((path, #reason.into()));
+                            }
+                        }
+                    }
+                    _ => proc_macro2::TokenStream::new(),
+                };
+                Some(quote! {
+                    if let Some(path) = self.#secret_file_ident.take() {
+                        #conflict
+                        let value = #read_secret;
+                        self.#field_ident = Some(value.map_err(|err| (path, err))?);
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Whether the file or clap struct has secret values given as files, directly or in nested sections
+    fn has_read_secrets(&self, prefix: &str, field_filter: &str) -> bool {
+        !self
+            .output_read_secrets_fields(prefix, field_filter)
+            .is_empty()
+    }
+
+    /// Output `read_secrets()` implementation for the file or clap struct, if any secret value can be
+    /// given as a file (always for a section, called by the enclosing struct)
+    fn output_read_secrets(&self, prefix: &str, field_filter: &str) -> proc_macro2::TokenStream {
+        let fields = self.output_read_secrets_fields(prefix, field_filter);
+        if fields.is_empty() && !self.section {
+            return proc_macro2::TokenStream::new();
+        }
+        let name = format!("{}{}", prefix, self.s.ident);
+        let ident = syn::Ident::new(&name, self.s.ident.span());
+        quote! {
+            impl #ident {
+                /// Read the secret values given as files, return the path and the error on failure
+                pub fn read_secrets(
+                    &mut self,
+                ) -> Result<(), (std::path::PathBuf, Box<dyn std::error::Error + Send + Sync>)> {
+                    #(#fields)*
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Output `load_file()` function
+    fn output_load_file(&self) -> proc_macro2::TokenStream {
+        let main_ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let sources_ident = self.suffixed_ident("Sources");
+        let source_ident = self.suffixed_ident("Source");
+        let config_file_ident = self.suffixed_ident("ConfigFile");
+        let parse_file = self.output_parse_file();
+        let (check_keys, check_keys_fn) = match self.strict {
+            Strict::Ignore => Default::default(),
+            Strict::Error => (
+                quote! { check_keys(path, &content)?; },
+                self.output_check_keys(),
+            ),
+            Strict::Warn => (
+                quote! { check_keys(path, &content, sources)?; },
+                self.output_check_keys(),
+            ),
+        };
+        let dir_files = self.output_dir_files();
+        let (parse_file_config, read_secrets) = self.output_load_file_config();
+        let interpolate = self.output_interpolate();
+        let stamp = if self.watch.is_some() {
+            // stamp before the file is read, not to miss a change while it is loaded
+            quote! { sources.files.push((path.to_path_buf(), file_stamp(path))); }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let load_dir_file = if self.include.is_some() {
+            quote! { load_file_included(&file, cfg, sources, parents)?; }
+        } else {
+            quote! { load_file(&file, cfg, sources)?; }
+        };
+        let (load_file, parents, include, include_fn) = match &self.include {
+            None => (
+                quote! { pub fn load_file },
+                proc_macro2::TokenStream::new(),
+                proc_macro2::TokenStream::new(),
+                proc_macro2::TokenStream::new(),
+            ),
+            Some(key) => (
+                quote! { fn load_file_included },
+                quote! { parents: &mut Vec<std::path::PathBuf>, },
+                quote! {
+                    // load the included files first, the including file having precedence
+                    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+                    if parents.contains(&canonical) {
+                        return Err(super::#error_ident::IncludeCycle {
+                            path: path.to_path_buf(),
+                        });
+                    }
+                    let includes: Includes = parse_file(path, &content)?;
+                    parents.push(canonical);
+                    for include in include_files(path, &includes.include)? {
+                        load_file_included(&include, cfg, sources, parents)?;
+                    }
+                    parents.pop();
+                },
+                self.output_include_files(key),
+            ),
+        };
+        quote! {
+            #include_fn
+
+            #load_file(
+                file: &super::#config_file_ident,
+                cfg: &mut super::#main_ident,
+                sources: &mut super::#sources_ident,
+                #parents
+            ) -> Result<(), super::#error_ident> {
+                let path = file.path.as_path();
+                #stamp
+                // load the files of a directory
+                if path.is_dir() {
+                    for file in dir_files(path)? {
+                        #load_dir_file
+                    }
+                    return Ok(());
+                }
+                // access file
+                let content = match std::fs::read_to_string(path) {
+                    Ok(content) => content,
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound && !file.required => {
+                        // optional file not found is not a problem...
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        // ... but everything else is -> propagate error
+                        return Err(super::#error_ident::FileOpen {
+                            path: path.to_path_buf(),
+                            source: err,
+                        });
+                    }
+                };
+
+                #include
+
+                // get parsed content
+                #parse_file_config
+                #check_keys
+                #read_secrets
+
+                // update config with content from the file
+                file_config.update(cfg, sources, &super::#source_ident::File(path.to_path_buf()));
+
+                Ok(())
+            }
+
+            #dir_files
+            #parse_file
+            #interpolate
+            #check_keys_fn
+        }
+    }
+
+    /// Output the parsing of the file struct, expanding its references if requested, and the reading of its
+    /// secret values given as files
+    fn output_load_file_config(&self) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        let name = format!("File{}", self.s.ident);
+        let ident = syn::Ident::new(&name, self.s.ident.span());
+        let error_ident = self.error_ident();
+        let parse = if self.interpolate {
+            quote! { parse_file_interpolated(path, &content, cfg)? }
+        } else {
+            quote! { parse_file(path, &content)? }
+        };
+        if self.has_read_secrets("File", "cli_settings_file") {
+            (
+                quote! { let mut file_config: #ident = #parse; },
+                quote! {
+                    file_config.read_secrets().map_err(|(path, source)| {
+                        super::#error_ident::SecretFile { path, source }
+                    })?;
+                },
+            )
+        } else {
+            (
+                quote! { let file_config: #ident = #parse; },
+                proc_macro2::TokenStream::new(),
+            )
+        }
+    }
+
+    /// Output `parse_file_interpolated()` function, expanding the references of the string values of a
+    /// configuration file before deserializing it, if requested
+    fn output_interpolate(&self) -> proc_macro2::TokenStream {
+        if !self.interpolate {
+            return proc_macro2::TokenStream::new();
+        }
+        let main_ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let settings = self
+            .fields
+            .iter()
+            .filter(|f| !f.secret && !f.nested)
+            .filter_map(|f| {
+                let ident = f.ident;
+                let key = f.file_key()?;
+                // output one entry (without separator)
+                Some(quote! {
+                    (#key, serde::Serialize::serialize(&cfg.#ident, ScalarSerializer).ok())
+                })
+            })
+            .collect::<Vec<_>>();
+        let vars = Self::output_vars();
+        let value = self.output_value();
+        let scalar_serializer = Self::output_scalar_serializer();
+        quote! {
+            /// Parse a configuration file, expanding the `${NAME}` references of its string values
+            fn parse_file_interpolated<T>(
+                path: &std::path::Path,
+                content: &str,
+                cfg: &super::#main_ident,
+            ) -> Result<T, super::#error_ident>
+            where
+                T: serde::de::DeserializeOwned,
+            {
+                let value: Value = parse_file(path, content)?;
+                let file = match &value {
+                    Value::Map(entries) => entries
+                        .iter()
+                        .filter_map(|(key, value)| Some((key.to_scalar()?, value.to_scalar()?)))
+                        .collect(),
+                    _ => std::collections::BTreeMap::new(),
+                };
+                let vars = Vars {
+                    file,
+                    settings: settings_values(cfg),
+                };
+                let value = vars.expand_value(value).map_err(|(name, reason)| {
+                    super::#error_ident::Interpolation {
+                        path: path.to_path_buf(),
+                        name,
+                        source: reason.into(),
+                    }
+                })?;
+                T::deserialize(value).map_err(|err| parse_error(path, None, err))
+            }
+
+            /// Current settings (default values, updated by the files loaded before), as strings if scalar
+            fn settings_values(cfg: &super::#main_ident) -> std::collections::BTreeMap<String, String> {
+                let values: Vec<(&str, Option<String>)> = vec![#(#settings),*];
+                values
+                    .into_iter()
+                    .filter_map(|(key, value)| Some((key.to_string(), value?)))
+                    .collect()
+            }
+
+            #vars
+            #value
+            #scalar_serializer
+        }
+    }
+
+    /// Output `Vars` struct, expanding the references
+    fn output_vars() -> proc_macro2::TokenStream {
+        quote! {
+            /// Values of the references of a configuration file: its top level scalar values, the current
+            /// settings, then the environment variables
+            struct Vars {
+                file: std::collections::BTreeMap<String, String>, // not expanded yet
+                settings: std::collections::BTreeMap<String, String>,
+            }
+
+            impl Vars {
+                /// Expand the references of the string values, not of the keys,
+                /// return the failed reference and the reason on failure
+                fn expand_value(&self, value: Value) -> Result<Value, (String, &'static str)> {
+                    Ok(match value {
+                        Value::Str(text) if text.contains('$') => {
+                            Value::Expanded(self.expand(&text, &mut Vec::new())?)
+                        }
+                        Value::Seq(items) => Value::Seq(
+                            items
+                                .into_iter()
+                                .map(|item| self.expand_value(item))
+                                .collect::<Result<_, _>>()?,
+                        ),
+                        Value::Map(entries) => Value::Map(
+                            entries
+                                .into_iter()
+                                .map(|(key, value)| Ok((key, self.expand_value(value)?)))
+                                .collect::<Result<_, _>>()?,
+                        ),
+                        value => value,
+                    })
+                }
+
+                /// Expand the `${NAME}` references of `text`, `$$` giving `$`
+                fn expand(
+                    &self,
+                    text: &str,
+                    stack: &mut Vec<String>,
+                ) -> Result<String, (String, &'static str)> {
+                    let mut res = String::with_capacity(text.len());
+                    let mut rest = text;
+                    while let Some(pos) = rest.find('$') {
+                        res.push_str(&rest[..pos]);
+                        rest = &rest[pos + 1..];
+                        if let Some(after) = rest.strip_prefix('$') {
+                            res.push('$');
+                            rest = after;
+                        } else if let Some(after) = rest.strip_prefix('{') {
+                            let Some(end) = after.find('}') else {
+                                return Err((after.to_string(), "unterminated reference"));
+                            };
+                            res.push_str(&self.lookup(&after[..end], stack)?);
+                            rest = &after[end + 1..];
+                        } else {
+                            res.push('$');
+                        }
+                    }
+                    res.push_str(rest);
+                    Ok(res)
+                }
+
+                /// Value of a reference, itself expanded if it is a value of the file
+                fn lookup(
+                    &self,
+                    name: &str,
+                    stack: &mut Vec<String>,
+                ) -> Result<String, (String, &'static str)> {
+                    if stack.iter().any(|parent| parent == name) {
+                        return Err((name.to_string(), "recursive reference"));
+                    }
+                    if let Some(value) = self.file.get(name) {
+                        stack.push(name.to_string());
+                        let value = self.expand(value, stack);
+                        stack.pop();
+                        return value;
+                    }
+                    if let Some(value) = self.settings.get(name) {
+                        return Ok(value.clone());
+                    }
+                    match std::env::var(name) {
+                        Ok(value) => Ok(value),
+                        Err(std::env::VarError::NotPresent) => {
+                            Err((name.to_string(), "undefined variable"))
+                        }
+                        Err(std::env::VarError::NotUnicode(_)) => {
+                            Err((name.to_string(), "invalid UTF-8 content"))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Output `Value` enum, holding a parsed configuration file whose strings are expanded,
+    /// with its deserialization from the file and into the file struct
+    fn output_value(&self) -> proc_macro2::TokenStream {
+        let visitor = Self::output_value_visitor();
+        let serialize = self.output_value_serialize();
+        let deserializer = Self::output_value_deserializer();
+        quote! {
+            /// Value of a configuration file
+            enum Value {
+                Null,
+                Bool(bool),
+                Int(i64),
+                UInt(u64),
+                Float(f64),
+                Str(String),
+                Expanded(String), // string with references, parsed if a boolean or a number is expected
+                Seq(Vec<Value>),
+                Map(Vec<(Value, Value)>),
+            }
+
+            impl Value {
+                fn to_scalar(&self) -> Option<String> {
+                    match self {
+                        Self::Bool(value) => Some(value.to_string()),
+                        Self::Int(value) => Some(value.to_string()),
+                        Self::UInt(value) => Some(value.to_string()),
+                        Self::Float(value) => Some(value.to_string()),
+                        Self::Str(value) | Self::Expanded(value) => Some(value.clone()),
+                        Self::Null | Self::Seq(_) | Self::Map(_) => None,
+                    }
+                }
+            }
+
+            #visitor
+
+            #deserializer
+
+            impl serde::de::IntoDeserializer<'_, serde::de::value::Error> for Value {
+                type Deserializer = Self;
+
+                fn into_deserializer(self) -> Self {
+                    self
+                }
+            }
+
+            #serialize
+        }
+    }
+
+    /// Output the deserialization of the file struct from `Value`
+    fn output_value_deserializer() -> proc_macro2::TokenStream {
+        // an expanded string is parsed if the field expects a boolean or a number
+        let parse = [
+            "bool", "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "f32", "f64",
+        ]
+        .iter()
+        .map(|ty| {
+            let span = proc_macro2::Span::call_site();
+            let deserialize = syn::Ident::new(&format!("deserialize_{ty}"), span);
+            let visit = syn::Ident::new(&format!("visit_{ty}"), span);
+            let ty = syn::Ident::new(ty, span);
+            // output one method
+            quote! {
+                fn #deserialize<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+                where
+                    V: serde::de::Visitor<'de>,
+                {
+                    match self {
+                        Self::Expanded(text) => visitor.#visit(text.parse::<#ty>().map_err(|err| {
+                            serde::de::Error::custom(format_args!("invalid value '{text}': {err}"))
+                        })?),
+                        value => value.deserialize_any(visitor),
+                    }
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+        quote! {
+            impl<'de> serde::Deserializer<'de> for Value {
+                type Error = serde::de::value::Error;
+
+                fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+                where
+                    V: serde::de::Visitor<'de>,
+                {
+                    match self {
+                        Self::Null => visitor.visit_unit(),
+                        Self::Bool(value) => visitor.visit_bool(value),
+                        Self::Int(value) => visitor.visit_i64(value),
+                        Self::UInt(value) => visitor.visit_u64(value),
+                        Self::Float(value) => visitor.visit_f64(value),
+                        Self::Str(value) | Self::Expanded(value) => visitor.visit_string(value),
+                        Self::Seq(items) => {
+                            let mut seq = serde::de::value::SeqDeserializer::new(items.into_iter());
+                            let value = visitor.visit_seq(&mut seq)?;
+                            seq.end()?;
+                            Ok(value)
+                        }
+                        Self::Map(entries) => {
+                            let mut map = serde::de::value::MapDeserializer::new(entries.into_iter());
+                            let value = visitor.visit_map(&mut map)?;
+                            map.end()?;
+                            Ok(value)
+                        }
+                    }
+                }
+
+                fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+                where
+                    V: serde::de::Visitor<'de>,
+                {
+                    match self {
+                        Self::Null => visitor.visit_none(),
+                        value => visitor.visit_some(value),
+                    }
+                }
+
+                fn deserialize_enum<V>(
+                    self,
+                    _name: &'static str,
+                    _variants: &'static [&'static str],
+                    visitor: V,
+                ) -> Result<V::Value, Self::Error>
+                where
+                    V: serde::de::Visitor<'de>,
+                {
+                    match self {
+                        // unit variant, or map with the variant as single key
+                        Self::Str(variant) | Self::Expanded(variant) => {
+                            visitor.visit_enum(serde::de::IntoDeserializer::into_deserializer(variant))
+                        }
+                        Self::Map(entries) if entries.len() == 1 => {
+                            visitor.visit_enum(serde::de::value::MapAccessDeserializer::new(
+                                serde::de::value::MapDeserializer::new(entries.into_iter()),
+                            ))
+                        }
+                        value => value.deserialize_any(visitor),
+                    }
+                }
+
+                fn deserialize_newtype_struct<V>(
+                    self,
+                    _name: &'static str,
+                    visitor: V,
+                ) -> Result<V::Value, Self::Error>
+                where
+                    V: serde::de::Visitor<'de>,
+                {
+                    visitor.visit_newtype_struct(self)
+                }
+
+                #(#parse)*
+
+                serde::forward_to_deserialize_any! {
+                    i128 u128 char str string bytes byte_buf unit unit_struct seq tuple
+                    tuple_struct map struct identifier ignored_any
+                }
+            }
+        }
+    }
+
+    /// Output the deserialization of `Value` from a configuration file
+    fn output_value_visitor() -> proc_macro2::TokenStream {
+        quote! {
+            impl<'de> serde::Deserialize<'de> for Value {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    deserializer.deserialize_any(ValueVisitor)
+                }
+            }
+
+            struct ValueVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for ValueVisitor {
+                type Value = Value;
+
+                fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "any value")
+                }
+
+                fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+                    Ok(Value::Bool(value))
+                }
+
+                fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+                    Ok(Value::Int(value))
+                }
+
+                fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+                    Ok(Value::UInt(value))
+                }
+
+                fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+                    Ok(Value::Float(value))
+                }
+
+                fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+                    Ok(Value::Str(value.to_string()))
+                }
+
+                fn visit_string<E>(self, value: String) -> Result<Value, E> {
+                    Ok(Value::Str(value))
+                }
+
+                fn visit_unit<E>(self) -> Result<Value, E> {
+                    Ok(Value::Null)
+                }
+
+                fn visit_none<E>(self) -> Result<Value, E> {
+                    Ok(Value::Null)
+                }
+
+                fn visit_some<D>(self, deserializer: D) -> Result<Value, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    serde::Deserialize::deserialize(deserializer)
+                }
+
+                fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+                where
+                    A: serde::de::SeqAccess<'de>,
+                {
+                    let mut items = Vec::new();
+                    while let Some(item) = seq.next_element()? {
+                        items.push(item);
+                    }
+                    Ok(Value::Seq(items))
+                }
+
+                fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+                where
+                    A: serde::de::MapAccess<'de>,
+                {
+                    let mut entries = Vec::new();
+                    while let Some(entry) = map.next_entry()? {
+                        entries.push(entry);
+                    }
+                    Ok(Value::Map(entries))
+                }
+
+                // tagged value of YAML, kept as a map with the tag as single key
+                fn visit_enum<A>(self, data: A) -> Result<Value, A::Error>
+                where
+                    A: serde::de::EnumAccess<'de>,
+                {
+                    let (variant, access) = data.variant()?;
+                    let value = serde::de::VariantAccess::newtype_variant(access)?;
+                    Ok(Value::Map(vec![(variant, value)]))
+                }
+            }
+        }
+    }
+
+    /// Output the serialization of `Value`, and `escape()` writing `$` as `$$` in its strings,
+    /// if the settings are serialized
+    fn output_value_serialize(&self) -> proc_macro2::TokenStream {
+        if !self.attrs.contains_key("cli_settings_serialize") {
+            return proc_macro2::TokenStream::new();
+        }
+        let toml_datetime = if self.formats.contains(&Format::Toml) {
+            quote! {
+                // TOML date and time, deserialized as a map with a private key
+                [(Self::Str(key), value)] if key == "$__toml_private_datetime" => {
+                    let mut datetime = serializer.serialize_struct("$__toml_private_Datetime", 1)?;
+                    serde::ser::SerializeStruct::serialize_field(
+                        &mut datetime,
+                        "$__toml_private_datetime",
+                        value,
+                    )?;
+                    serde::ser::SerializeStruct::end(datetime)
+                }
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        quote! {
+            impl serde::Serialize for Value {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    match self {
+                        Self::Null => serializer.serialize_none(),
+                        Self::Bool(value) => serializer.serialize_bool(*value),
+                        Self::Int(value) => serializer.serialize_i64(*value),
+                        Self::UInt(value) => serializer.serialize_u64(*value),
+                        Self::Float(value) => serializer.serialize_f64(*value),
+                        Self::Str(value) | Self::Expanded(value) => serializer.serialize_str(value),
+                        Self::Seq(items) => serializer.collect_seq(items),
+                        Self::Map(entries) => match entries.as_slice() {
+                            #toml_datetime
+                            _ => serializer.collect_map(entries.iter().map(|(key, value)| (key, value))),
+                        },
+                    }
+                }
+            }
+
+            /// Write `$` as `$$` in the string values, not to be taken for a reference when loaded
+            fn escape(value: Value) -> Value {
+                match value {
+                    Value::Str(text) | Value::Expanded(text) => Value::Str(text.replace('$', "$$")),
+                    Value::Seq(items) => Value::Seq(items.into_iter().map(escape).collect()),
+                    Value::Map(entries) => Value::Map(
+                        entries
+                            .into_iter()
+                            .map(|(key, value)| (key, escape(value)))
+                            .collect(),
+                    ),
+                    value => value,
+                }
+            }
+        }
+    }
+
+    /// Output `ScalarSerializer` struct, serializing a setting value into a string if it is a scalar
+    fn output_scalar_serializer() -> proc_macro2::TokenStream {
+        let scalars = [
+            "bool", "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "f32", "f64", "char",
+        ]
+        .iter()
+        .map(|ty| {
+            let span = proc_macro2::Span::call_site();
+            let serialize = syn::Ident::new(&format!("serialize_{ty}"), span);
+            let ty = syn::Ident::new(ty, span);
+            // output one method
+            quote! {
+                fn #serialize(self, value: #ty) -> Result<String, Self::Error> {
+                    Ok(value.to_string())
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+        let compound = Self::output_scalar_serializer_compound();
+        quote! {
+            /// Serializer of a setting value into a string, failing if the value is not a scalar
+            struct ScalarSerializer;
+
+            fn not_scalar() -> serde::de::value::Error {
+                serde::ser::Error::custom("not a scalar")
+            }
+
+            impl serde::Serializer for ScalarSerializer {
+                type Ok = String;
+                type Error = serde::de::value::Error;
+                type SerializeSeq = serde::ser::Impossible<String, Self::Error>;
+                type SerializeTuple = serde::ser::Impossible<String, Self::Error>;
+                type SerializeTupleStruct = serde::ser::Impossible<String, Self::Error>;
+                type SerializeTupleVariant = serde::ser::Impossible<String, Self::Error>;
+                type SerializeMap = serde::ser::Impossible<String, Self::Error>;
+                type SerializeStruct = serde::ser::Impossible<String, Self::Error>;
+                type SerializeStructVariant = serde::ser::Impossible<String, Self::Error>;
+
+                #(#scalars)*
+
+                fn serialize_str(self, value: &str) -> Result<String, Self::Error> {
+                    Ok(value.to_string())
+                }
+
+                fn serialize_bytes(self, _value: &[u8]) -> Result<String, Self::Error> {
+                    Err(not_scalar())
+                }
+
+                fn serialize_none(self) -> Result<String, Self::Error> {
+                    Err(not_scalar())
+                }
+
+                fn serialize_some<T>(self, value: &T) -> Result<String, Self::Error>
+                where
+                    T: serde::Serialize + ?Sized,
+                {
+                    value.serialize(self)
+                }
+
+                fn serialize_unit(self) -> Result<String, Self::Error> {
+                    Err(not_scalar())
+                }
+
+                fn serialize_unit_struct(self, _name: &'static str) -> Result<String, Self::Error> {
+                    Err(not_scalar())
+                }
+
+                fn serialize_unit_variant(
+                    self,
+                    _name: &'static str,
+                    _index: u32,
+                    variant: &'static str,
+                ) -> Result<String, Self::Error> {
+                    Ok(variant.to_string())
+                }
+
+                fn serialize_newtype_struct<T>(
+                    self,
+                    _name: &'static str,
+                    value: &T,
+                ) -> Result<String, Self::Error>
+                where
+                    T: serde::Serialize + ?Sized,
+                {
+                    value.serialize(self)
+                }
+
+                fn serialize_newtype_variant<T>(
+                    self,
+                    _name: &'static str,
+                    _index: u32,
+                    _variant: &'static str,
+                    _value: &T,
+                ) -> Result<String, Self::Error>
+                where
+                    T: serde::Serialize + ?Sized,
+                {
+                    Err(not_scalar())
+                }
+
+                #compound
+            }
+        }
+    }
+
+    /// Output the serialization methods of `ScalarSerializer` for the compound values, all failing
+    fn output_scalar_serializer_compound() -> proc_macro2::TokenStream {
+        quote! {
+            fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+                Err(not_scalar())
+            }
+
+            fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+                Err(not_scalar())
+            }
+
+            fn serialize_tuple_struct(
+                self,
+                _name: &'static str,
+                _len: usize,
+            ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+                Err(not_scalar())
+            }
+
+            fn serialize_tuple_variant(
+                self,
+                _name: &'static str,
+                _index: u32,
+                _variant: &'static str,
+                _len: usize,
+            ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+                Err(not_scalar())
+            }
+
+            fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+                Err(not_scalar())
+            }
+
+            fn serialize_struct(
+                self,
+                _name: &'static str,
+                _len: usize,
+            ) -> Result<Self::SerializeStruct, Self::Error> {
+                Err(not_scalar())
+            }
+
+            fn serialize_struct_variant(
+                self,
+                _name: &'static str,
+                _index: u32,
+                _variant: &'static str,
+                _len: usize,
+            ) -> Result<Self::SerializeStructVariant, Self::Error> {
+                Err(not_scalar())
+            }
+        }
+    }
+
+    /// Output `dir_files()` function, getting the configuration files of a directory
+    fn output_dir_files(&self) -> proc_macro2::TokenStream {
+        let error_ident = self.error_ident();
+        let config_file_ident = self.suffixed_ident("ConfigFile");
+        let extensions = self
+            .formats
+            .iter()
+            .flat_map(|f| f.extensions())
+            .collect::<Vec<_>>();
+        quote! {
+            /// Get the configuration files of a directory, in lexical order
+            fn dir_files(
+                path: &std::path::Path,
+            ) -> Result<Vec<super::#config_file_ident>, super::#error_ident> {
+                const EXTENSIONS: &[&str] = &[#(#extensions),*];
+                let entries = std::fs::read_dir(path).map_err(|err| super::#error_ident::FileOpen {
+                    path: path.to_path_buf(),
+                    source: err,
+                })?;
+                let mut files = entries
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .filter(|path| {
+                        path.is_file()
+                            && !path
+                                .file_name()
+                                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+                            && path
+                                .extension()
+                                .and_then(|ext| ext.to_str())
+                                .is_some_and(|ext| EXTENSIONS.contains(&ext))
+                    })
+                    .collect::<Vec<_>>();
+                files.sort();
+                Ok(files.into_iter().map(super::#config_file_ident::optional).collect())
+            }
+        }
+    }
+
+    /// Output `include_files()` function, getting the files included by a configuration file,
+    /// and `load_file()` loading a configuration file with its included files
+    fn output_include_files(&self, key: &str) -> proc_macro2::TokenStream {
+        let main_ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let sources_ident = self.suffixed_ident("Sources");
+        let config_file_ident = self.suffixed_ident("ConfigFile");
+        quote! {
+            pub fn load_file(
+                file: &super::#config_file_ident,
+                cfg: &mut super::#main_ident,
+                sources: &mut super::#sources_ident,
+            ) -> Result<(), super::#error_ident> {
+                load_file_included(file, cfg, sources, &mut Vec::new())
+            }
+
+            /// Files included by a configuration file
+            #[derive(serde::Deserialize)]
+            struct Includes {
+                #[serde(rename = #key, default)]
+                include: Vec<String>,
+            }
+
+            /// Get the included files, relative to the including file;
+            /// a pattern in the file name gives the matching files, in lexical order
+            fn include_files(
+                path: &std::path::Path,
+                patterns: &[String],
+            ) -> Result<Vec<super::#config_file_ident>, super::#error_ident> {
+                let dir = path.parent().unwrap_or_else(|| std::path::Path::new(""));
+                let mut files = Vec::new();
+                for pattern in patterns {
+                    let include = dir.join(pattern);
+                    let name = include.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+                    if !name.contains(['*', '?']) {
+                        files.push(super::#config_file_ident::required(include));
+                        continue;
+                    }
+                    let name = name.chars().collect::<Vec<_>>();
+                    let include_dir = include.parent().unwrap_or(dir);
+                    let entries = match std::fs::read_dir(include_dir) {
+                        Ok(entries) => entries,
+                        Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                        Err(err) => {
+                            return Err(super::#error_ident::FileOpen {
+                                path: include_dir.to_path_buf(),
+                                source: err,
+                            })
+                        }
+                    };
+                    let mut matches = entries
+                        .filter_map(Result::ok)
+                        .map(|entry| entry.path())
+                        .filter(|path| {
+                            path.file_name()
+                                .and_then(|file_name| file_name.to_str())
+                                .is_some_and(|file_name| {
+                                    glob_match(&name, &file_name.chars().collect::<Vec<_>>())
+                                })
+                        })
+                        .collect::<Vec<_>>();
+                    matches.sort();
+                    files.extend(matches.into_iter().map(super::#config_file_ident::optional));
+                }
+                Ok(files)
+            }
+
+            /// Match a file name against a pattern with `*` and `?` wildcards
+            fn glob_match(pattern: &[char], name: &[char]) -> bool {
+                match pattern.split_first() {
+                    None => name.is_empty(),
+                    Some(('*', rest)) => (0..=name.len()).any(|i| glob_match(rest, &name[i..])),
+                    Some(('?', rest)) => !name.is_empty() && glob_match(rest, &name[1..]),
+                    Some((c, rest)) => name.first() == Some(c) && glob_match(rest, &name[1..]),
+                }
+            }
+        }
+    }
+
+    /// Output the listing of the keys of the file fields in `keys`, after `prefix`, with the keys of the nested
+    /// sections listed by the sections
+    fn output_known_keys(&self) -> proc_macro2::TokenStream {
+        // keys of the file fields, renamed by serde if requested
+        let plain_keys = self
+            .fields
+            .iter()
+            .filter(|f| !f.nested)
+            .filter_map(Field::file_key)
+            .chain(
+                self.fields
+                    .iter()
+                    .filter(|f| f.has_secret_file())
+                    .filter_map(|f| f.file_key().map(|key| format!("{key}_file"))),
+            )
+            .chain(self.include.clone())
+            .collect::<Vec<_>>();
+        let nested_keys = self
+            .fields
+            .iter()
+            .filter(|f| f.nested)
+            .filter_map(|f| {
+                let key = f.file_key()?;
+                let ty = f.nested_ty("File");
+                // output the keys of one section
+                Some(quote! {
+                    keys.push((format!("{prefix}{}", #key), true));
+                    <#ty>::cli_settings_keys(&format!("{prefix}{}.", #key), keys);
+                })
+            })
+            .collect::<Vec<_>>();
+        quote! {
+            let plain_keys: &[&str] = &[#(#plain_keys),*];
+            keys.extend(plain_keys.iter().map(|key| (format!("{prefix}{key}"), false)));
+            #(#nested_keys)*
+        }
+    }
+
+    /// Output the listing of the keys of a section, called by the enclosing struct to check the keys
+    fn output_section_keys(&self) -> proc_macro2::TokenStream {
+        let name = format!("File{}", self.s.ident);
+        let file_ident = syn::Ident::new(&name, self.s.ident.span());
+        let known_keys = self.output_known_keys();
+        quote! {
+            impl #file_ident {
+                /// List the keys of the section in the configuration files, after `prefix`,
+                /// with whether the key is a nested section
+                pub fn cli_settings_keys(prefix: &str, keys: &mut Vec<(String, bool)>) {
+                    #known_keys
+                }
+            }
+        }
+    }
+
+    /// Output `check_keys()` function, looking for unknown keys in a configuration file
+    fn output_check_keys(&self) -> proc_macro2::TokenStream {
+        let error_ident = self.error_ident();
+        let sources_ident = self.suffixed_ident("Sources");
+        let known_keys = self.output_known_keys();
+        let keys = quote! {
+            /// Keys of the settings in the configuration files, after `prefix`, with whether the key is a nested
+            /// section; the keys of a nested section follow its key and a dot (`database.host`)
+            fn known_keys(prefix: &str, keys: &mut Vec<(String, bool)>) {
+                #known_keys
+            }
+
+            /// Keys of a configuration file, with the keys of the nested maps
+            #[derive(serde::Deserialize)]
+            #[serde(untagged)]
+            enum Keys {
+                Map(std::collections::BTreeMap<String, Keys>),
+                Other(#[allow(dead_code)] serde::de::IgnoredAny),
+            }
+
+            fn file_keys(
+                path: &std::path::Path,
+                content: &str,
+                known: &[(String, bool)],
+            ) -> Result<Vec<String>, super::#error_ident> {
+                let keys: std::collections::BTreeMap<String, Keys> = parse_file(path, content)?;
+                let mut unknown = Vec::new();
+                unknown_keys(known, "", keys, &mut unknown);
+                Ok(unknown)
+            }
+
+            /// Collect the unknown keys, checking the keys of the nested sections as well
+            fn unknown_keys(
+                known: &[(String, bool)],
+                prefix: &str,
+                keys: std::collections::BTreeMap<String, Keys>,
+                unknown: &mut Vec<String>,
+            ) {
+                for (key, value) in keys {
+                    let key = format!("{prefix}{key}");
+                    match (known.iter().find(|(known, _)| *known == key), value) {
+                        (None, _) => unknown.push(key),
+                        (Some((_, true)), Keys::Map(keys)) => {
+                            unknown_keys(known, &format!("{key}."), keys, unknown);
+                        }
+                        (Some(_), _) => (),
+                    }
+                }
+            }
+        };
+        if self.strict == Strict::Warn {
+            return quote! {
+                #keys
+
+                fn check_keys(
+                    path: &std::path::Path,
+                    content: &str,
+                    sources: &mut super::#sources_ident,
+                ) -> Result<(), super::#error_ident> {
+                    let mut known = Vec::new();
+                    known_keys("", &mut known);
+                    for key in file_keys(path, content, &known)? {
+                        sources.unknown_keys.push((path.to_path_buf(), key));
+                    }
+                    Ok(())
+                }
+            };
+        }
+        quote! {
+            #keys
+
+            fn check_keys(path: &std::path::Path, content: &str) -> Result<(), super::#error_ident> {
+                let mut known = Vec::new();
+                known_keys("", &mut known);
+                match file_keys(path, content, &known)?.into_iter().next() {
+                    Some(key) => Err(super::#error_ident::UnknownKey {
+                        path: path.to_path_buf(),
+                        suggestion: suggest(&key, &known),
+                        key,
+                    }),
+                    None => Ok(()),
+                }
+            }
+
+            /// Closest known key, if close enough
+            fn suggest(key: &str, known: &[(String, bool)]) -> Option<String> {
+                known
+                    .iter()
+                    .map(|(known, _)| (distance(key, known), known))
+                    .filter(|(dist, _)| dist * 3 <= key.chars().count() + 2)
+                    .min_by_key(|(dist, _)| *dist)
+                    .map(|(_, known)| known.clone())
+            }
+
+            /// Levenshtein distance between two strings
+            fn distance(a: &str, b: &str) -> usize {
+                let b = b.chars().collect::<Vec<_>>();
+                let mut row = (0..=b.len()).collect::<Vec<_>>();
+                for (i, ca) in a.chars().enumerate() {
+                    let mut prev = row[0];
+                    row[0] = i + 1;
+                    for (j, cb) in b.iter().enumerate() {
+                        let cur = row[j + 1];
+                        row[j + 1] = if ca == *cb {
+                            prev
+                        } else {
+                            1 + prev.min(row[j]).min(cur)
+                        };
+                        prev = cur;
+                    }
+                }
+                row[b.len()]
+            }
+        }
+    }
+
+    /// Output `parse_file()` function, selecting the format from the file extension, and `parse_format()`
+    fn output_parse_file(&self) -> proc_macro2::TokenStream {
+        let format_ident = self.suffixed_ident("Format");
+        let formats = self
+            .formats
+            .iter()
             .map(|f| {
-                // field tokens
-                let field_attrs = attr_keys
-                    .iter()
-                    .map(|k| f.attrs.get(*k).unwrap_or(&empty))
-                    .collect::<Vec<_>>();
-                let field_vis = f.vis;
-                let field_ident = f.ident;
-                let field_ty = f.ty;
-                let (field_ty_start, field_ty_end) = if prefix.is_empty() || !f.opt {
-                    // no prefix, field with configured type
-                    (empty.clone(), empty.clone())
-                } else {
-                    // prefix, field with Option<configured type>
-                    (
-                        proc_macro2::TokenStream::from_str("Option<").unwrap(),
-                        proc_macro2::TokenStream::from_str(">").unwrap(),
-                    )
-                };
-                // struct tokens
-                        // output one field (without separator)
+                let variant = f.variant();
+                let parse = f.output_parse();
+                // output one match arm
                 quote! {
-                    #(#field_attrs)* #field_vis #field_ident: #field_ty_start #field_ty #field_ty_end
+                    super::#format_ident::#variant => #parse,
                 }
             })
             .collect::<Vec<_>>();
-        // output the whole struct
+        let error_ident = self.error_ident();
+        let text_location = if self.formats.contains(&Format::Toml) {
+            quote! {
+                /// Get the location (line and column, 1-based) of a byte offset in the text
+                fn text_location(text: &str, offset: usize) -> (usize, usize) {
+                    let before = &text[..offset.min(text.len())];
+                    let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+                    (
+                        before.matches('\n').count() + 1,
+                        before[line_start..].chars().count() + 1,
+                    )
+                }
+            }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
         quote! {
-            #(#attrs)* #vis #struct_token #ident
+            fn parse_file<T>(path: &std::path::Path, content: &str) -> Result<T, super::#error_ident>
+            where
+                T: serde::de::DeserializeOwned,
             {
-                #(#fields),*
+                match super::#format_ident::from_path(path) {
+                    Some(format) => parse_format(path, content, format),
+                    None => Err(super::#error_ident::FileFormat {
+                        path: path.to_path_buf(),
+                    }),
+                }
+            }
+
+            fn parse_format<T>(
+                path: &std::path::Path,
+                content: &str,
+                format: super::#format_ident,
+            ) -> Result<T, super::#error_ident>
+            where
+                T: serde::de::DeserializeOwned,
+            {
+                match format {
+                    #(#formats)*
+                }
+            }
+
+            fn parse_error<E>(
+                path: &std::path::Path,
+                location: Option<(usize, usize)>,
+                err: E,
+            ) -> super::#error_ident
+            where
+                E: std::error::Error + Send + Sync + 'static,
+            {
+                // the error of the format may quote the value of a secret field: keep only the message naming
+                // the field, the location given by some formats being the one of the enclosing section
+                let message = err.to_string();
+                if let Some(start) = message.find(#SECRET_ERROR) {
+                    let rest = &message[start + #SECRET_ERROR.len()..];
+                    let key = rest.split('\'').next().unwrap_or_default();
+                    return super::#error_ident::FileParse {
+                        path: path.to_path_buf(),
+                        line: None,
+                        column: None,
+                        source: format!("{}{key}'", #SECRET_ERROR).into(),
+                    };
+                }
+                super::#error_ident::FileParse {
+                    path: path.to_path_buf(),
+                    line: location.map(|(line, _)| line),
+                    column: location.map(|(_, column)| column),
+                    source: Box::new(err),
+                }
             }
+
+            #text_location
         }
     }
 
-    /// Output the main structure
-    fn output_main_struct(&self) -> proc_macro2::TokenStream {
-        self.output_struct("", None, &["_", "doc"])
+    /// Whether at least one field can be set from an environment variable
+    fn has_env(&self) -> bool {
+        self.fields.iter().any(|f| f.env.is_some())
     }
-    /// Output the file structure
-    fn output_file_struct(&self) -> proc_macro2::TokenStream {
-        self.output_struct("File", Some("cli_settings_file"), &["cli_settings_file"])
+
+    /// Whether at least one field is secret
+    fn has_secrets(&self) -> bool {
+        self.fields.iter().any(|f| f.secret)
     }
-    /// Output the clap structure
-    fn output_clap_struct(&self) -> proc_macro2::TokenStream {
-        self.output_struct(
-            "Clap",
-            Some("cli_settings_clap"),
-            &["doc", "cli_settings_clap"],
-        )
+
+    /// Whether a field value shall be checked
+    fn has_checks(&self) -> bool {
+        self.fields.iter().any(|f| !f.checks.is_empty())
     }
 
-    /// Output Default implementation for the main struct
-    fn output_main_struct_default(&self) -> proc_macro2::TokenStream {
-        let default = proc_macro2::TokenStream::from_str("Default::default()").unwrap();
-        let ident = &self.s.ident;
-        let fields = self
+    /// Output `validate()` function, if needed
+    fn output_validate(&self) -> proc_macro2::TokenStream {
+        if !self.has_checks() {
+            return proc_macro2::TokenStream::new();
+        }
+        let main_ident = &self.s.ident;
+        let error_ident = self.error_ident();
+        let sources_ident = self.suffixed_ident("Sources");
+        let source_ident = self.suffixed_ident("Source");
+        let checks = self
             .fields
             .iter()
-            .map(|f| {
-                let field_ident = f.ident;
-                let field_default = f.attrs.get("cli_settings_default").unwrap_or(&default);
-                // output one field (without separator)
-                quote! {
-                    #field_ident: #field_default
-                }
-            })
+            .flat_map(|f| f.checks.iter().map(move |check| check.output(f)))
             .collect::<Vec<_>>();
         quote! {
-            impl Default for #ident {
-                fn default() -> Self {
-                    Self{
-                        #(#fields),*
-                    }
-                }
+            pub fn validate(
+                cfg: &super::#main_ident,
+                sources: &super::#sources_ident,
+            ) -> Result<(), super::#error_ident> {
+                #(#checks)*
+                Ok(())
             }
-        }
-    }
 
-    /// Output `build()` implementation for the main struct
-    fn output_main_struct_build(&self) -> proc_macro2::TokenStream {
-        let ident = &self.s.ident;
-        quote! {
-            impl #ident {
-                pub fn build<F, I, T>(cfg_files: F, args: I) -> anyhow::Result<Self>
-                where
-                    F: IntoIterator<Item = std::path::PathBuf>,
-                    I: IntoIterator<Item = T>,
-                    T: Into<std::ffi::OsString> + Clone,
-                {
-                    let mut cfg = Self::default();
-                    for file in cfg_files {
-                        _cli_settings_derive::load_file(&file, &mut cfg)?;
-                    }
-                    _cli_settings_derive::parse_cli_args(args, &mut cfg)?;
-                    Ok(cfg)
+            fn invalid<E>(field: &str, sources: &super::#sources_ident, reason: E) -> super::#error_ident
+            where
+                E: Into<Box<dyn std::error::Error + Send + Sync>>,
+            {
+                super::#error_ident::Validation {
+                    field: field.to_string(),
+                    origin: sources.get(field).cloned().unwrap_or(super::#source_ident::Default),
+                    source: reason.into(),
                 }
             }
         }
     }
 
-    /// Output `update()` implementation for the file struct
-    fn output_struct_update(&self, prefix: &str, field_filter: &str) -> proc_macro2::TokenStream {
+    /// Output `load_env()` function
+    fn output_load_env(&self) -> proc_macro2::TokenStream {
+        if !self.has_env() {
+            return proc_macro2::TokenStream::new();
+        }
         let main_ident = &self.s.ident;
-        let name = format!("{}{}", prefix, self.s.ident);
-        let ident = syn::Ident::new(&name, self.s.ident.span());
+        let error_ident = self.error_ident();
+        let sources_ident = self.suffixed_ident("Sources");
+        let source_ident = self.suffixed_ident("Source");
         let fields = self
             .fields
             .iter()
-            .filter(|f| f.attrs.contains_key(field_filter))
-            .map(|f| {
-                let field_ident = f.ident;
-                // output one field (without separator)
-                if f.opt {
-                    quote! {
-                        if let Some(param) = self.#field_ident {
-                            cfg.#field_ident = param;
+            .filter_map(|f| f.env.as_ref().map(|env| (f, env)))
+            .map(|(f, env)| {
+                let field_name = f.name();
+                let field_ty = f.ty;
+                let merge = f.output_merge(&quote! { value });
+                // secret value given as a file, rejected along with the value
+                let (conflict, secret_file) = if f.has_secret_file() {
+                    let env_file = format!("{env}_FILE");
+                    let reason = format!("conflicting with the environment variable '{env}'");
+                    let read_secret = f.output_read_secret();
+                    let conflict = quote! {
+                        if let Some(path) = std::env::var_os(#env_file) {
+                            return Err(super::#error_ident::SecretFile {
+                                path: path.into(),
+                                source: #reason.into(),
+                            });
                         }
-                    }
+                    };
+                    let secret_file = quote! {
+                        else if let Some(path) = std::env::var_os(#env_file) {
+                            let path = std::path::PathBuf::from(path);
+                            let value: #field_ty = #read_secret.map_err(|source| {
+                                super::#error_ident::SecretFile { path, source }
+                            })?;
+                            #merge
+                            sources.set(#field_name, super::#source_ident::Env(#env_file.to_string()));
+                        }
+                    };
+                    (conflict, secret_file)
                 } else {
-                    quote! {
-                        cfg.#field_ident = self.#field_ident;
-                    }
+                    Default::default()
+                };
+                // output one field (without separator)
+                quote! {
+                    if let Some(value) = std::env::var_os(#env) {
+                        #conflict
+                        let value: #field_ty = parse_env(#env, value)?;
+                        #merge
+                        sources.set(#field_name, super::#source_ident::Env(#env.to_string()));
+                    } #secret_file
                 }
             })
             .collect::<Vec<_>>();
         quote! {
-            impl #ident {
-                fn update(self, cfg: &mut super::#main_ident) {
-                    #(#fields)*
-                }
+            pub fn load_env(
+                cfg: &mut super::#main_ident,
+                sources: &mut super::#sources_ident,
+            ) -> Result<(), super::#error_ident> {
+                #(#fields)*
+                Ok(())
+            }
+
+            fn parse_env<T>(name: &str, value: std::ffi::OsString) -> Result<T, super::#error_ident>
+            where
+                T: std::str::FromStr,
+                T::Err: std::fmt::Display,
+            {
+                value
+                    .to_str()
+                    .ok_or_else(|| "invalid UTF-8 content".to_string())
+                    .and_then(|value| value.parse::<T>().map_err(|err| err.to_string()))
+                    .map_err(|err| super::#error_ident::Env {
+                        name: name.to_string(),
+                        source: err.into(),
+                    })
             }
         }
     }
-    /// Output the file struct `update()`
-    fn output_file_struct_update(&self) -> proc_macro2::TokenStream {
-        self.output_struct_update("File", "cli_settings_file")
-    }
-    /// Output the clap struct `update()`
-    fn output_clap_struct_update(&self) -> proc_macro2::TokenStream {
-        self.output_struct_update("Clap", "cli_settings_clap")
-    }
 
-    /// Output `load_file()` function
-    fn output_load_file(&self) -> proc_macro2::TokenStream {
+    /// Output `parse_cli_args()` function, or `load_with_config_arg()` with the `--config` argument
+    fn output_parse_cli_args(&self) -> proc_macro2::TokenStream {
         let main_ident = &self.s.ident;
-        let name = format!("File{}", self.s.ident);
+        let error_ident = self.error_ident();
+        let sources_ident = self.suffixed_ident("Sources");
+        let source_ident = self.suffixed_ident("Source");
+        let name = format!("Clap{}", self.s.ident);
         let ident = syn::Ident::new(&name, self.s.ident.span());
-        quote! {
-            pub fn load_file(path: &std::path::Path, cfg: &mut super::#main_ident) -> anyhow::Result<()> {
-                // access file
-                let file = std::fs::File::open(path);
-                if let Err(err) = file {
-                    if err.kind() == std::io::ErrorKind::NotFound {
-                        // file not found is not a problem...
-                        return Ok(());
+        let cli_error = quote! {
+            fn cli_error(err: clap::Error) -> super::#error_ident {
+                match err.kind() {
+                    // help shown for a missing argument or subcommand is a usage error, as for clap
+                    clap::error::ErrorKind::DisplayHelp | clap::error::ErrorKind::DisplayVersion => {
+                        super::#error_ident::Help(err)
                     }
-                    // ... but everything else is -> propagate error
-                    return Err(err).context(format!(
-                        "Failed to open the configuration file '{}'",
-                        path.display()
-                    ));
+                    _ => super::#error_ident::Cli(err),
                 }
-                let file = file.unwrap();
-
-                // get parsed content
-                let file_config: #ident = serde_yaml::from_reader(file).with_context(|| {
-                    format!(
-                        "Failed to parse the configuration file '{}'",
-                        path.display()
-                    )
+            }
+        };
+        let config_file_ident = self.suffixed_ident("ConfigFile");
+        let read_secrets = if self.has_read_secrets("Clap", "cli_settings_clap") {
+            quote! {
+                cli_args.read_secrets().map_err(|(path, source)| {
+                    super::#error_ident::SecretFile { path, source }
                 })?;
-
-                // update config with content from the file
-                file_config.update(cfg);
-
-                Ok(())
             }
-        }
-    }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
+        let Some((config_arg, _)) = self.config_arg else {
+            let cli_args = if read_secrets.is_empty() {
+                quote! { cli_args }
+            } else {
+                quote! { mut cli_args }
+            };
+            return quote! {
+                pub fn parse_cli_args<I, T>(
+                    args: I,
+                    cfg: &mut super::#main_ident,
+                    sources: &mut super::#sources_ident,
+                ) -> Result<(), super::#error_ident>
+                where
+                    I: IntoIterator<Item = T>,
+                    T: Into<std::ffi::OsString> + Clone,
+                {
+                    let #cli_args = #ident ::try_parse_from(args).map_err(cli_error)?;
+                    #read_secrets
+                    cli_args.update(cfg, sources, &super::#source_ident::Cli);
+                    Ok(())
+                }
 
-    /// Output `parse_cli_args()` function
-    fn output_parse_cli_args(&self) -> proc_macro2::TokenStream {
-        let main_ident = &self.s.ident;
-        let name = format!("Clap{}", self.s.ident);
-        let ident = syn::Ident::new(&name, self.s.ident.span());
+                #cli_error
+            };
+        };
+        let default_files = if config_arg == ConfigArg::Replace {
+            quote! {
+                if cli_args.cli_settings_config.is_empty() {
+                    files.extend(cfg_files.into_iter().map(Into::into));
+                }
+            }
+        } else {
+            quote! { files.extend(cfg_files.into_iter().map(Into::into)); }
+        };
+        let load_env = if self.has_env() {
+            quote! { load_env(cfg, sources)?; }
+        } else {
+            proc_macro2::TokenStream::new()
+        };
         quote! {
-            pub fn parse_cli_args<I, T>(args: I, cfg: &mut super::#main_ident) -> anyhow::Result<()>
+            pub fn load_with_config_arg<F, I, T>(
+                cfg_files: F,
+                args: I,
+                cfg: &mut super::#main_ident,
+                sources: &mut super::#sources_ident,
+            ) -> Result<(), super::#error_ident>
             where
+                F: IntoIterator,
+                F::Item: Into<super::#config_file_ident>,
                 I: IntoIterator<Item = T>,
                 T: Into<std::ffi::OsString> + Clone,
             {
-                let cli_args = #ident ::parse_from(args);
-                cli_args.update(cfg);
+                let mut cli_args = #ident ::try_parse_from(args).map_err(cli_error)?;
+                #read_secrets
+                let mut files = Vec::new();
+                if !cli_args.cli_settings_no_config {
+                    #default_files
+                    // files explicitly given on the command line shall exist
+                    files.extend(
+                        cli_args
+                            .cli_settings_config
+                            .drain(..)
+                            .map(super::#config_file_ident::required),
+                    );
+                }
+                for file in &files {
+                    load_file(file, cfg, sources)?;
+                }
+                #load_env
+                cli_args.update(cfg, sources, &super::#source_ident::Cli);
                 Ok(())
             }
+
+            #cli_error
         }
     }
 
@@ -373,9 +4079,11 @@ impl<'a> SettingStruct<'a> {
 /// - create an instance with default values (provided by annotations)
 /// - read each possible configuration file, if it exists:
 ///   - update the fields that are defined in the configuration file
+/// - read the environment variables, and update the relevant fields with the provided value
 /// - parse the command line arguments, and update the relevant fields with the provided argument
 ///
-/// By using annotations, each field can be configurable via the configuration file(s) and/or the command line.
+/// By using annotations, each field can be configurable via the configuration file(s), the environment
+/// and/or the command line.
 ///
 /// cli-settings-derive can be seen as a top layer above
 /// - [serde](https://docs.rs/serde) for the file configuration parsing
@@ -393,10 +4101,321 @@ impl<'a> SettingStruct<'a> {
 ///     file(s). The passed string if any will be extra annotation(s) to the file parsing struct.
 ///   - `#[cli_settings_clap = "xxx"]` to indicate that the field shall be a command line argument.
 ///     The passed string (if any) will be extra annotation(s) to the command line parsing struct.
+///   - `#[cli_settings_env = "XXX"]` to indicate that the field shall be read from the environment
+///     variable `XXX`. See [Environment variables](#environment-variables).
+///   - `#[cli_settings_merge = "xxx"]` to select how a collection field is merged when set by several
+///     sources. See [Collection merge](#collection-merge).
+///   - `#[cli_settings_validate = "xxx"]` to check the field value once all sources are merged.
+///     See [Validation](#validation).
 /// - For each field, provide documentation (with ///) to generate the help message via clap.
 /// - In your application code, call the `Settings::build()` method with the list of config files to read
 ///   and the command line arguments to get your application configuration.
 ///
+/// A config file can also be a directory, such as `/etc/app/conf.d/`: its files with the extension of an enabled
+/// format (see [Configuration file formats](#configuration-file-formats)) are loaded in lexical order, skipping
+/// the hidden files.
+///
+/// A missing config file is skipped. To require some of the files, give them as `SettingsConfigFile` to
+/// `Settings::build_with_config_files()` or `Settings::try_build_with_config_files()`, the same as `build()` and
+/// `try_build_with_sources()`: the absence of a file given as `SettingsConfigFile::required(path)` is a
+/// `SettingsError::FileOpen` error, and `SettingsConfigFile::optional(path)` is the same as the plain path. These
+/// methods also take the files as plain paths (`PathBuf`, `&Path`, `String` or `&str`); an empty list of files
+/// shall then be typed, for example `Vec::<PathBuf>::new()`.
+///
+/// `Settings::build()` terminates the process on help or version requests and on invalid command line
+/// arguments, as done by clap. `Settings::try_build()` returns these cases as a `SettingsError` instead,
+/// distinguishing help and version requests (`SettingsError::Help`) from invalid arguments
+/// (`SettingsError::Cli`, including the help shown by clap for a missing argument or subcommand); the caller
+/// can print the message with `err.print()` on the inner `clap::Error`.
+///
+/// `Settings::build_with_sources()` and `Settings::try_build_with_sources()` also return a `SettingsSources`
+/// report, giving for each field the source of its value: `SettingsSource::Default`,
+/// `SettingsSource::File(path)`, `SettingsSource::Env(name)` or `SettingsSource::Cli`.
+///
+/// ### Errors
+///
+/// The generated `SettingsError` enum (named after the struct) lists the possible failures, so that the
+/// caller can handle each of them: `FileOpen`, `FileFormat` and `FileParse` for the configuration files,
+/// with the location of the error in the file when provided by the format, `Env` for the environment
+/// variables, `Help` and `Cli` for the command line arguments. The enum is `#[non_exhaustive]`: the options
+/// described below come with their own variants, and later versions may add new ones, so a `match` on it needs
+/// a wildcard arm.
+///
+/// ### Validation
+///
+/// The field annotation `#[cli_settings_validate = "xxx"]` gives a comma separated list of checks of the
+/// field value, run by `Settings::build()` once the default value, the configuration files, the environment
+/// variables and the command line arguments are merged:
+/// - `non_empty`: the value shall not be empty (the type shall provide `is_empty()`)
+/// - `range(1..=65535)`: the value shall be in the range
+/// - `one_of("debug", "info")`: the value shall be equal to one of the listed values
+/// - `path_exists`: the value shall be an existing path
+/// - `path::to::check`: custom check, a function with signature `fn check(value: &FieldType) -> Result<(), E>`,
+///   where `E` converts into `Box<dyn std::error::Error + Send + Sync>` (`String`, `&str`, any error type)
+///
+/// For example `#[cli_settings_validate = "non_empty, crate::check_name"]`. The first failing check returns a
+/// `SettingsError::Validation` error, giving the field and the source of its value; the reason of the rejection
+/// is the error source.
+///
+/// Checks involving several fields are provided by a method of the struct, given with the struct annotation
+/// `#[cli_settings_validate_with = "Settings::check"]`, with signature `fn check(&self) -> Result<(), E>`
+/// (same `E` as above). It is called by `Settings::build()` after the field checks, and its error is returned as
+/// `SettingsError::InvalidSettings`, the reason being the error source.
+///
+/// ### Unknown keys
+///
+/// By default, the keys of a configuration file that match no field are ignored. With the struct annotation
+/// `#[cli_settings_strict]`, such a key is a `SettingsError::UnknownKey` error, giving the file, the key and
+/// the closest known key if any (`Unknown key 'alpah' in the configuration file 'x.yml', did you mean 'alpha'?`).
+/// With `#[cli_settings_strict = "warn"]`, the unknown keys are only reported by
+/// `SettingsSources::unknown_keys()`, as returned by `Settings::build_with_sources()`.
+///
+/// The keys of the nested sections are checked as well, and reported after the key of the section:
+/// `database.hots`. The keys of a map field are not checked. A field renamed with `#[serde(rename = "xxx")]` is
+/// taken into account, but not a struct level `#[serde(rename_all = "xxx")]`.
+///
+/// ### Configuration file discovery
+///
+/// With the struct annotation `#[cli_settings_app = "mytool"]`, `Settings::default_config_paths()` returns the
+/// usual configuration files of the application, following the XDG base directory specification, from the
+/// lowest to the highest precedence:
+/// - `/etc/mytool/config.<ext>`
+/// - `<dir>/mytool/config.<ext>` for each directory of `$XDG_CONFIG_DIRS` (default `/etc/xdg`), the first
+///   directory of the variable having the highest precedence
+/// - `$XDG_CONFIG_HOME/mytool/config.<ext>` (default `$HOME/.config`)
+/// - `./.mytool.<ext>`, in the current directory
+///
+/// where `<ext>` is each extension of the enabled formats (`yml` and `yaml` for YAML...). Missing files are
+/// skipped as usual. `Settings::build_discovered(args)` builds the settings from these files and the command line
+/// arguments, and `Settings::try_build_discovered(args)` does the same without exiting, like
+/// `Settings::try_build()`.
+///
+/// ### Configuration files from the command line
+///
+/// With the struct annotation `#[cli_settings_config_arg]`, the command line gets two more arguments:
+/// - `--config <PATH>` (can be repeated): configuration files to load instead of the files passed to
+///   `Settings::build()`; with `#[cli_settings_config_arg = "extend"]`, they are loaded after these files.
+///   These files are required: a missing file is an error.
+/// - `--no-config`: do not load any configuration file
+///
+/// The value of the annotation is a comma separated list of options: `replace` (default) or `extend`, and
+/// `short` to add the short flag `-c` to `--config`, for example `#[cli_settings_config_arg = "extend, short"]`.
+///
+/// The command line is then parsed before the configuration files are loaded; its arguments still apply
+/// after the configuration files and the environment variables. The clap struct shall not define another
+/// `--config` or `--no-config` argument, nor another `-c` flag with the `short` option.
+///
+/// ### Included configuration files
+///
+/// With the struct annotation `#[cli_settings_include]`, a configuration file can include other configuration
+/// files, listed by its top level key `include` (or the key given by `#[cli_settings_include = "xxx"]`):
+/// ```yaml
+/// include: [common.yml, "conf.d/*.yml"]
+/// alpha: 3
+/// ```
+/// The included files are loaded before the including file, which has precedence over them, in the listed
+/// order. Relative paths are resolved against the directory of the including file. The file name (not the
+/// directories) may contain the `*` and `?` wildcards, the matching files being loaded in lexical order; a file
+/// without wildcard shall exist. A file including itself, directly or not, is a `SettingsError::IncludeCycle`
+/// error.
+///
+/// ### Variable interpolation
+///
+/// With the struct annotation `#[cli_settings_interpolate]`, the string values of each configuration file can
+/// reference other values with `${NAME}`:
+/// ```yaml
+/// cache_dir: "${HOME}/.cache/app"
+/// log_file: "${data_dir}/app.log"
+/// port: ${APP_PORT}
+/// price: "$$5"
+/// ```
+/// `NAME` is first looked up in the top level values (strings, numbers or booleans) of the file itself, then in
+/// the current settings: the default values, updated by the files loaded before, including the included files;
+/// then in the environment variables. The types of the settings shall implement `serde::Serialize`, the
+/// secret fields and the nested sections cannot be referenced.
+///
+/// The file is parsed first, then its string values are expanded, then it is deserialized: the keys, the
+/// comments and the included file names are never expanded, and the inserted value needs no quoting. An
+/// expanded string can give a boolean or a number, such as `port` above. `$$` gives a literal `$`, and a `$` not
+/// followed by `{` is kept as is. An undefined variable, a reference to a value referencing it back, or a `${`
+/// without `}` is a `SettingsError::Interpolation` error, with the path of the file. Once expanded, an invalid
+/// value is a `SettingsError::FileParse` error without location.
+///
+/// With `#[cli_settings_serialize]`, `Settings::to_file_string()`, `Settings::write_config()` and
+/// `Settings::sample_config()` write `$` as `$$` in the string values, so that the file is loaded back as is.
+///
+/// ### Comparing settings
+///
+/// With the struct annotation `#[cli_settings_diff]`, `settings.diff(&other)` lists a `SettingsFieldChange`
+/// for each field whose value differs in `other`, with the field name and both values formatted with `Debug`,
+/// for example to log the changes on reload. The fields of a nested section are named after the section field:
+/// `database.host`. The field types shall implement `PartialEq` and `Debug`.
+///
+/// ### Watching the configuration files
+///
+/// With the struct annotation `#[cli_settings_watch]`, `Settings::watch(files, args, callback)` builds the
+/// settings like `Settings::try_build()`, then polls the files it looked up every second, or every `N`
+/// milliseconds with `#[cli_settings_watch = "N"]`: the given files and directories, the files of these
+/// directories, the files given by `--config` and the included files, even the missing optional ones. When one
+/// of them changes, the settings are built again from the same files and command line arguments, which keep
+/// their precedence, and `callback` gets `Ok((&old, &new))`; the watched files are then the ones looked up by
+/// this build. If the new settings fail to build, `callback` gets the error, and the previous settings remain
+/// the current ones.
+///
+/// `watch()` also returns a `SettingsWatcher` handle, whose `reload()` builds the settings again even if no file
+/// changed. The watch stops when all the handles are dropped. The settings struct shall implement `Clone`, and
+/// the callback runs on the watch thread.
+///
+/// With the additional struct annotation `#[cli_settings_watch_sighup]`, a SIGHUP received by the process also
+/// builds the settings again, as daemons usually expect; it is noticed at the next polling. The application
+/// shall then depend on [`signal-hook`](https://docs.rs/signal-hook), and `watch()` returns
+/// `SettingsError::Signal` if the handler cannot be registered. The handler is registered on Unix only, and
+/// unregistered when the watch stops.
+///
+/// ### Configuration file formats
+///
+/// The format of each configuration file is selected from its extension:
+/// - `.yml` or `.yaml`: [YAML](https://docs.rs/serde_yaml)
+/// - `.toml`: [TOML](https://docs.rs/toml), requires the `toml` feature
+/// - `.json`: [JSON](https://docs.rs/serde_json), requires the `json` feature
+/// - `.json5`: [JSON5](https://docs.rs/json5), requires the `json5` feature
+///
+/// Files with any other extension are parsed with the default format, the first enabled format of the
+/// list above unless the struct is annotated with `#[cli_settings_format = "xxx"]` (`yaml`, `toml`, `json`,
+/// `json5`). With `#[cli_settings_format = "none"]`, such files are rejected with an error.
+///
+/// With the struct annotation `#[cli_settings_formats = "yaml, json"]`, the generated code supports only the
+/// listed formats, among the enabled ones; see [Cargo features](#cargo-features).
+///
+/// ### Writing configuration files
+///
+/// With the struct annotation `#[cli_settings_serialize]`, the methods `Settings::to_file_string(format)` and
+/// `Settings::write_config(path)` output the fields that can be set from a configuration file, in a format
+/// that can be loaded back. The file parsing struct shall then also derive `serde::Serialize`
+/// (`#[cli_settings_file = "#[derive(serde::Deserialize, serde::Serialize)]"]`), and the field types
+/// shall implement `Clone`.
+///
+/// `Settings::sample_config(format)` generates a template configuration file, with the default value of each
+/// field that can be set from a configuration file, preceded by its documentation as comments (except for
+/// JSON, which does not support comments).
+///
+/// ### Secret fields
+///
+/// A field annotated with `#[cli_settings_secret]`, such as a password or an API token, is loaded as usual but
+/// never output by the generated code:
+/// - the macro implements `Debug` for the struct, printing `***` for the secret fields; the struct shall not
+///   derive `Debug` itself (a derive following `#[cli_settings]` is rejected by the macro, one preceding it
+///   gives a conflicting implementation error)
+/// - `Settings::to_file_string()` and `Settings::write_config()` omit the secret fields, and
+///   `Settings::sample_config()` does not list them
+/// - `Settings::diff()` reports the change of a secret field with `***` as values
+/// - an invalid value of a secret field in a configuration file gives a `SettingsError::FileParse` error
+///   naming the field, without the error of the format, which may quote the value, nor its location (unless
+///   the field has its own deserialization attribute, such as `deserialize_with` or `serde_as`)
+///
+/// In a nested section, annotate the secret fields of the section.
+///
+/// Following the Docker and Kubernetes secrets convention, the value of a secret field can also be read from a
+/// file, whose content is trimmed and parsed with the `FromStr` implementation of the field type:
+/// - `password_file: /run/secrets/db` in a configuration file, for the field `password`
+/// - `--password-file <PATH>` on the command line, if the field has a long argument
+/// - `APP_PASSWORD_FILE` in the environment, if the field is read from `APP_PASSWORD`
+///
+/// A relative path is resolved from the current directory. A file that cannot be read or parsed is a
+/// `SettingsError::SecretFile` error. Giving both the value and its file in the same source is an error as
+/// well: a `SettingsError::Cli` error on the command line, a `SettingsError::SecretFile` error in a
+/// configuration file or in the environment.
+///
+/// ### Cargo features
+///
+/// The cargo features select the code generated by the macro:
+/// - `yaml` (default): YAML configuration files, the application shall depend on `serde_yaml`
+/// - `toml`: TOML configuration files, the application shall depend on `toml`
+/// - `json`: JSON configuration files, the application shall depend on `serde_json`
+/// - `json5`: JSON5 configuration files, the application shall depend on `json5`
+/// - `env` (default): environment variables, see [Environment variables](#environment-variables)
+///
+/// At least one configuration file format shall be enabled. Whatever the features, the application
+/// shall depend on `clap` (with `derive` feature) and `serde` (with `derive` feature).
+/// For example, a TOML only application would use:
+/// ```toml
+/// cli-settings-derive = { version = "0.3", default-features = false, features = ["toml", "env"] }
+/// ```
+///
+/// Cargo unifies the features of a dependency across a workspace: when a crate of the workspace enables `toml`,
+/// the code generated for the other crates supports TOML as well, and they shall then also depend on `toml`.
+/// To avoid this, annotate the struct with the formats it supports, for example
+/// `#[cli_settings_formats = "yaml"]`: the generated code only supports the listed formats, whatever the other
+/// enabled features. Each listed format still requires its feature.
+///
+/// ### Environment variables
+///
+/// Environment variables are applied after the configuration files and before the command line arguments.
+/// The value of the variable is parsed with `std::str::FromStr`, so the field type shall implement it.
+///
+/// The variable name is either given explicitly with `#[cli_settings_env = "APP_ALPHA"]`, or derived from
+/// the field name when the struct is annotated with `#[cli_settings_env_prefix = "APP_"]`:
+/// `#[cli_settings_env]` on the field `alpha` then reads the variable `APP_ALPHA`.
+///
+/// ### Collection merge
+///
+/// By default, the value of a field is replaced by each source providing it. The field annotation
+/// `#[cli_settings_merge = "xxx"]` selects another strategy, applied to the default value, then to each
+/// configuration file and command line argument setting the field:
+/// - `replace`: the value replaces the previous one (default behavior)
+/// - `append`: the items of the value are added after the previous ones (for a map, the new value of a key
+///   replaces the previous one)
+/// - `prepend`: the items of the value are added before the previous ones (for a map, the previous value of
+///   a key is kept)
+/// - `union`: as `append`, skipping the items that are already present; not for a map, the type shall provide
+///   a `contains()` method (`Vec`, `HashSet`, `BTreeSet`...)
+/// - `deep`: for a map only, the entries of a configuration file replace the previous ones key by key, and a
+///   `null` value removes the key (`aliases: { ll: "ls -l", la: null }`); the command line behaves as `append`.
+///   The field type shall be written with its key and value types (`HashMap<String, String>`), not as a type
+///   alias. TOML has no `null` value, so a TOML file cannot remove a key
+///
+/// The field type shall implement `Extend`, `IntoIterator` and `Default` (`Vec`, `HashMap`, `BTreeSet`...).
+/// A repeated command line argument (`--plugin a --plugin b`) provides all its values at once. A field with a
+/// merge strategy cannot be read from an environment variable, whose value cannot be split into items.
+///
+/// ### Nested settings
+///
+/// Settings can be grouped in sections, each section being a struct annotated with `#[cli_settings]` and
+/// `#[cli_settings_nested]`. A field with such a type and the annotation `#[cli_settings_nested]` is then
+/// merged field by field: a configuration file only sets the fields of the section it provides, the others
+/// keep their value from the previous files or their default value.
+///
+/// ```ignore
+/// #[cli_settings]
+/// #[cli_settings_nested = "db"]
+/// #[cli_settings_file = "#[derive(serde::Deserialize)]"]
+/// #[cli_settings_clap = "#[derive(clap::Args)]"]
+/// pub struct Database {
+///     /// database host
+///     #[cli_settings_file]
+///     #[cli_settings_clap = "#[arg(long)]"]
+///     pub host: String,
+/// }
+///
+/// #[cli_settings]
+/// #[cli_settings_file = "#[derive(serde::Deserialize)]"]
+/// #[cli_settings_clap = "#[derive(clap::Parser)]"]
+/// pub struct Settings {
+///     #[cli_settings_nested]
+///     #[cli_settings_file]
+///     #[cli_settings_clap]
+///     pub database: Database,
+/// }
+/// ```
+///
+/// The clap struct of a section shall derive `clap::Args`; its arguments are flattened in the command line
+/// of the enclosing struct. The optional value of the struct annotation `#[cli_settings_nested = "db"]` prefixes
+/// the long arguments of the section: `--db-host` in the example above. The prefix belongs to the section type,
+/// so when several fields have the same section type, only one of them can be annotated with `#[cli_settings_clap]`.
+/// A section provides no `build()` method, and its fields cannot be read from environment variables. The source of
+/// a nested field reports the last source that set any of its fields. To write configuration files, the section
+/// shall also be annotated with `#[cli_settings_serialize]`; to compare the settings, with `#[cli_settings_diff]`.
+///
 /// ### User-defined struct
 ///
 /// A user-defined struct can be used as a field in the configuration struct.
@@ -440,7 +4459,24 @@ impl<'a> SettingStruct<'a> {
 ///
 /// ## Basic example
 ///
-/// ```
+#[cfg_attr(
+    any(
+        feature = "yaml",
+        feature = "toml",
+        feature = "json",
+        feature = "json5"
+    ),
+    doc = "```"
+)]
+#[cfg_attr(
+    not(any(
+        feature = "yaml",
+        feature = "toml",
+        feature = "json",
+        feature = "json5"
+    )),
+    doc = "```ignore"
+)]
 /// use cli_settings_derive::cli_settings;
 ///
 /// #[cli_settings]
@@ -495,12 +4531,29 @@ pub fn cli_settings(
         Err(e) => return e.to_compile_error().into(),
     };
 
+    if ss.section {
+        return ss.output_section().into();
+    }
+
     let main_struct = ss.output_main_struct();
     let main_struct_default = ss.output_main_struct_default();
     let main_struct_build = ss.output_main_struct_build();
+    let error = ss.output_error();
+    let sources = ss.output_sources();
+    let config_file = ss.output_config_file();
+    let format = ss.output_format();
+    let serialize = ss.output_serialize();
+    let discovery = ss.output_discovery();
+    let diff = ss.output_diff();
+    let watch = ss.output_watch();
+    let file_stamp = ss.output_file_stamp();
     let file_struct = ss.output_file_struct();
     let file_struct_update = ss.output_file_struct_update();
     let load_file = ss.output_load_file();
+    let to_file_string = ss.output_to_file_string();
+    let diff_function = ss.output_diff_function();
+    let load_env = ss.output_load_env();
+    let validate = ss.output_validate();
     let clap_struct = ss.output_clap_struct();
     let clap_struct_update = ss.output_clap_struct_update();
     let parse_cli_args = ss.output_parse_cli_args();
@@ -510,9 +4563,16 @@ pub fn cli_settings(
         #main_struct
         #main_struct_default
         #main_struct_build
+        #error
+        #sources
+        #config_file
+        #format
+        #serialize
+        #discovery
+        #diff
+        #watch
 
         mod _cli_settings_derive {
-            use anyhow::Context;
             use clap::Parser;
             use super::*;
 
@@ -520,6 +4580,13 @@ pub fn cli_settings(
             #file_struct_update
 
             #load_file
+            #file_stamp
+            #to_file_string
+            #diff_function
+
+            #load_env
+
+            #validate
 
             #clap_struct
             #clap_struct_update
@@ -0,0 +1,141 @@
+//! Test the `--config` and `--no-config` command line arguments
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_config_arg = "replace, short"]
+pub struct Settings {
+    /// gamma setting explanation
+    #[cli_settings_file]
+    pub gamma: u64,
+
+    /// delta setting explanation
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub delta: u32,
+}
+
+/// Settings loading the configuration files of the command line after the default ones
+mod extend {
+    #[derive(PartialEq, Debug)]
+    #[cli_settings]
+    #[cli_settings_file = "#[derive(serde::Deserialize)]"]
+    #[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+    #[cli_settings_config_arg = "extend"]
+    pub struct Settings {
+        /// gamma setting explanation
+        #[cli_settings_file]
+        pub gamma: u64,
+
+        /// delta setting explanation
+        #[cli_settings_file]
+        #[cli_settings_clap = "#[arg(long)]"]
+        pub delta: u32,
+    }
+}
+
+/// Test the configuration files given on the command line, replacing the default ones
+#[test]
+pub fn replace() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let test_dir = std::path::Path::new(&project_dir).join("tests");
+    let usage1 = test_dir.join("usage1.yml");
+    let usage2 = test_dir.join("usage2.yml");
+
+    // default configuration files
+    let cfg = Settings::try_build(vec![usage1.clone()], ["test-bin"])?;
+    assert_eq!(
+        cfg,
+        Settings {
+            gamma: 728,
+            delta: 65535
+        }
+    );
+
+    // configuration files of the command line, the arguments applying after them
+    let cfg = Settings::try_build(
+        vec![usage1.clone()],
+        [
+            "test-bin".into(),
+            "-c".into(),
+            usage2.clone().into_os_string(),
+        ],
+    )?;
+    assert_eq!(
+        cfg,
+        Settings {
+            gamma: 0,
+            delta: 32
+        }
+    );
+    let cfg = Settings::try_build(
+        Vec::new(),
+        [
+            "test-bin".into(),
+            "--delta=1".into(),
+            "--config".into(),
+            usage2.into_os_string(),
+            "--config".into(),
+            usage1.clone().into_os_string(),
+        ],
+    )?;
+    assert_eq!(
+        cfg,
+        Settings {
+            gamma: 728,
+            delta: 1
+        }
+    );
+
+    // no configuration file
+    let cfg = Settings::try_build(vec![usage1], ["test-bin", "--no-config"])?;
+    assert_eq!(cfg, Settings { gamma: 0, delta: 0 });
+    assert!(matches!(
+        Settings::try_build(Vec::new(), ["test-bin", "--no-config", "-c", "x.yml"]),
+        Err(SettingsError::Cli(_))
+    ));
+
+    // configuration file of the command line shall exist
+    assert!(matches!(
+        Settings::try_build(Vec::new(), ["test-bin", "-c", "does-not-exist.yml"]),
+        Err(SettingsError::FileOpen { .. })
+    ));
+    Ok(())
+}
+
+/// Test the configuration files given on the command line, loaded after the default ones
+#[test]
+pub fn extend() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let test_dir = std::path::Path::new(&project_dir).join("tests");
+
+    let cfg = extend::Settings::try_build(
+        vec![test_dir.join("usage1.yml")],
+        [
+            "test-bin".into(),
+            "--config".into(),
+            test_dir.join("usage2.yml").into_os_string(),
+        ],
+    )?;
+    assert_eq!(
+        cfg,
+        extend::Settings {
+            gamma: 728,
+            delta: 32
+        }
+    );
+
+    // no short flag without the `short` option
+    assert!(matches!(
+        extend::Settings::try_build(Vec::new(), ["test-bin", "-c", "x.yml"]),
+        Err(extend::SettingsError::Cli(_))
+    ));
+    Ok(())
+}
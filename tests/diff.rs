@@ -0,0 +1,89 @@
+//! Test the comparison of the settings, field by field
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_nested = "db"]
+#[cli_settings_file = "#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Args)]"]
+#[cli_settings_diff]
+pub struct Database {
+    /// database host
+    #[cli_settings_default = "\"localhost\".to_string()"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub host: String,
+
+    /// database port
+    #[cli_settings_default = "5432"]
+    #[cli_settings_file]
+    pub port: u16,
+}
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_diff]
+pub struct Settings {
+    /// database settings
+    #[cli_settings_nested]
+    #[cli_settings_file]
+    #[cli_settings_clap]
+    pub database: Database,
+
+    /// delta setting explanation
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub delta: u32,
+
+    /// tags setting explanation
+    #[cli_settings_file]
+    pub tags: Vec<String>,
+}
+
+/// Test the fields listed by `diff()`
+#[test]
+pub fn diff() -> anyhow::Result<()> {
+    let old = Settings::try_build(Vec::new(), ["test-bin"])?;
+    assert!(old.diff(&old).is_empty());
+
+    let new = Settings::try_build(
+        Vec::new(),
+        ["test-bin", "--db-host", "db.example.com", "--delta", "42"],
+    )?;
+    assert_eq!(
+        old.diff(&new),
+        vec![
+            SettingsFieldChange {
+                field: "database.host".to_string(),
+                old: "\"localhost\"".to_string(),
+                new: "\"db.example.com\"".to_string(),
+            },
+            SettingsFieldChange {
+                field: "delta".to_string(),
+                old: "0".to_string(),
+                new: "42".to_string(),
+            },
+        ]
+    );
+
+    let other = Settings {
+        tags: vec!["a".to_string()],
+        ..Settings::default()
+    };
+    assert_eq!(
+        old.diff(&other),
+        vec![SettingsFieldChange {
+            field: "tags".to_string(),
+            old: "[]".to_string(),
+            new: "[\"a\"]".to_string(),
+        }]
+    );
+    Ok(())
+}
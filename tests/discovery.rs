@@ -0,0 +1,63 @@
+//! Test the discovery of the configuration files of the application
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+use std::path::PathBuf;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_app = "cli-settings-test"]
+pub struct Settings {
+    /// alpha setting explanation
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub alpha: u32,
+
+    /// beta setting explanation
+    #[cli_settings_file]
+    pub beta: u32,
+}
+
+/// Test `default_config_paths()`, `build_discovered()` and `try_build_discovered()` methods
+#[test]
+pub fn build_discovered() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("discovery");
+    let config_home = dir.join("home");
+    std::fs::create_dir_all(config_home.join("cli-settings-test"))?;
+    std::fs::write(
+        config_home.join("cli-settings-test/config.yml"),
+        "alpha: 1\nbeta: 2\n",
+    )?;
+    std::env::set_var("XDG_CONFIG_HOME", &config_home);
+    std::env::set_var("XDG_CONFIG_DIRS", "/first:/second");
+
+    let paths = Settings::default_config_paths()
+        .into_iter()
+        .filter(|p| p.extension().is_some_and(|ext| ext == "yml"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            PathBuf::from("/etc/cli-settings-test/config.yml"),
+            PathBuf::from("/second/cli-settings-test/config.yml"),
+            PathBuf::from("/first/cli-settings-test/config.yml"),
+            config_home.join("cli-settings-test/config.yml"),
+            PathBuf::from(".cli-settings-test.yml"),
+        ]
+    );
+
+    let cfg = Settings::build_discovered(["test-bin", "--alpha=3"])?;
+    assert_eq!(cfg, Settings { alpha: 3, beta: 2 });
+
+    let cfg = Settings::try_build_discovered(["test-bin"])?;
+    assert_eq!(cfg, Settings { alpha: 1, beta: 2 });
+    let err = Settings::try_build_discovered(["test-bin", "--alpha=x"]).unwrap_err();
+    assert!(matches!(err, SettingsError::Cli(_)), "{err:?}");
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! Test usage of `cli_settings` with environment variables
+
+#![cfg(all(feature = "yaml", feature = "env"))]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[serde_with::serde_as]#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_env_prefix = "CLI_SETTINGS_TEST_ENV_"]
+pub struct Settings {
+    /// alpha setting explanation
+    #[cli_settings_env = "CLI_SETTINGS_TEST_ALPHA"]
+    pub alpha: bool,
+
+    /// gamma setting explanation
+    #[cli_settings_default = "1 << 63"]
+    #[cli_settings_file]
+    #[cli_settings_env]
+    pub gamma: u64,
+
+    /// delta setting explanation
+    #[cli_settings_default = "42"]
+    #[cli_settings_file]
+    #[cli_settings_env]
+    pub delta: u32,
+
+    /// epsilon setting explanation
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(short, long)]"]
+    #[cli_settings_env]
+    pub epsilon: u32,
+
+    /// type setting explanation, with a raw identifier
+    #[cli_settings_env]
+    pub r#type: u32,
+}
+
+/// Test `build()` method
+/// Environment variables override config files, and are overridden by command line arguments
+#[test]
+pub fn build() -> anyhow::Result<()> {
+    let expected = Settings {
+        alpha: true,   // set by environment
+        gamma: 728,    // set by one config file
+        delta: 7,      // set by config files and environment
+        epsilon: 1024, // set by config file, environment and command line
+        r#type: 5,     // set by environment, without the raw identifier prefix
+    };
+
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let test_dir = std::path::Path::new(&project_dir).join("tests");
+
+    std::env::set_var("CLI_SETTINGS_TEST_ALPHA", "true");
+    std::env::set_var("CLI_SETTINGS_TEST_ENV_DELTA", "7");
+    std::env::set_var("CLI_SETTINGS_TEST_ENV_EPSILON", "3");
+    std::env::set_var("CLI_SETTINGS_TEST_ENV_TYPE", "5");
+
+    let (cfg, sources) = Settings::build_with_sources(
+        vec![test_dir.join("usage1.yml"), test_dir.join("usage2.yml")],
+        ["test-bin", "-e", "1024"],
+    )?;
+    assert_eq!(cfg, expected);
+    assert_eq!(
+        sources.get("delta"),
+        Some(&SettingsSource::Env("CLI_SETTINGS_TEST_ENV_DELTA".to_string()))
+    );
+    Ok(())
+}
@@ -24,22 +24,793 @@ impl Default for Settings {
     }
 }
 impl Settings {
-    pub fn build<F, I, T>(cfg_files: F, args: I) -> anyhow::Result<Self>
+    pub fn build<F, I, T>(cfg_files: F, args: I) -> Result<Self, SettingsError>
     where
         F: IntoIterator<Item = std::path::PathBuf>,
         I: IntoIterator<Item = T>,
         T: Into<std::ffi::OsString> + Clone,
+    {
+        Self::build_with_sources(cfg_files, args).map(|(cfg, _)| cfg)
+    }
+    /// Same as `build()`, but return the command line errors, including help and version
+    /// requests, instead of exiting the process
+    pub fn try_build<F, I, T>(cfg_files: F, args: I) -> Result<Self, SettingsError>
+    where
+        F: IntoIterator<Item = std::path::PathBuf>,
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        Self::try_build_with_sources(cfg_files, args).map(|(cfg, _)| cfg)
+    }
+    /// Same as `build()`, also returning the source of each setting value
+    pub fn build_with_sources<F, I, T>(
+        cfg_files: F,
+        args: I,
+    ) -> Result<(Self, SettingsSources), SettingsError>
+    where
+        F: IntoIterator<Item = std::path::PathBuf>,
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        match Self::try_build_with_sources(cfg_files, args) {
+            Err(SettingsError::Help(err) | SettingsError::Cli(err)) => err.exit(),
+            res => res,
+        }
+    }
+    /// Same as `try_build()`, also returning the source of each setting value
+    pub fn try_build_with_sources<F, I, T>(
+        cfg_files: F,
+        args: I,
+    ) -> Result<(Self, SettingsSources), SettingsError>
+    where
+        F: IntoIterator<Item = std::path::PathBuf>,
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        Self::try_build_with_config_files(cfg_files, args)
+    }
+}
+impl Settings {
+    /** Same as `build()`, with the configuration files given as `SettingsConfigFile`, some of them being
+ possibly required, or as paths*/
+    pub fn build_with_config_files<F, I, T>(
+        cfg_files: F,
+        args: I,
+    ) -> Result<Self, SettingsError>
+    where
+        F: IntoIterator,
+        F::Item: Into<SettingsConfigFile>,
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        match Self::try_build_with_config_files(cfg_files, args) {
+            Ok((cfg, _)) => Ok(cfg),
+            Err(SettingsError::Help(err) | SettingsError::Cli(err)) => err.exit(),
+            Err(err) => Err(err),
+        }
+    }
+    /** Same as `try_build_with_sources()`, with the configuration files given as `SettingsConfigFile`,
+ some of them being possibly required, or as paths*/
+    pub fn try_build_with_config_files<F, I, T>(
+        cfg_files: F,
+        args: I,
+    ) -> Result<(Self, SettingsSources), SettingsError>
+    where
+        F: IntoIterator,
+        F::Item: Into<SettingsConfigFile>,
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
     {
         let mut cfg = Self::default();
+        let mut sources = SettingsSources::default();
         for file in cfg_files {
-            _cli_settings_derive::load_file(&file, &mut cfg)?;
+            _cli_settings_derive::load_file(&file.into(), &mut cfg, &mut sources)?;
+        }
+        _cli_settings_derive::parse_cli_args(args, &mut cfg, &mut sources)?;
+        Ok((cfg, sources))
+    }
+}
+/// Error returned by `Settings::build()` and `Settings::try_build()`
+#[non_exhaustive]
+pub enum SettingsError {
+    /// Help or version message requested on the command line
+    Help(clap::Error),
+    /// Invalid command line arguments
+    Cli(clap::Error),
+    /// Failure to open a configuration file
+    FileOpen {
+        /// path of the configuration file
+        path: std::path::PathBuf,
+        /// underlying error
+        source: std::io::Error,
+    },
+    /// Configuration file with an unsupported extension
+    FileFormat {
+        /// path of the configuration file
+        path: std::path::PathBuf,
+    },
+    /// Failure to parse a configuration file
+    FileParse {
+        /// path of the configuration file
+        path: std::path::PathBuf,
+        /// line of the error (1-based), if provided by the format
+        line: Option<usize>,
+        /// column of the error (1-based), if provided by the format
+        column: Option<usize>,
+        /// underlying error
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Configuration file including itself, directly or not, with `cli_settings_include`
+    IncludeCycle {
+        /// path of the configuration file
+        path: std::path::PathBuf,
+    },
+    /// Reference of a configuration file that cannot be expanded, with `cli_settings_interpolate`
+    Interpolation {
+        /// path of the configuration file
+        path: std::path::PathBuf,
+        /// referenced name
+        name: String,
+        /// reason of the failure
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Key of a configuration file matching no setting, with `cli_settings_strict`
+    UnknownKey {
+        /// path of the configuration file
+        path: std::path::PathBuf,
+        /// unknown key
+        key: String,
+        /// closest known key, if any
+        suggestion: Option<String>,
+    },
+    /// Setting value rejected by a check of `cli_settings_validate`
+    Validation {
+        /// name of the setting
+        field: String,
+        /// source of the rejected value
+        origin: SettingsSource,
+        /// reason of the rejection
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Settings rejected by the method of `cli_settings_validate_with`
+    InvalidSettings {
+        /// reason of the rejection
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Failure to serialize the settings
+    Serialize {
+        /// underlying error
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Failure to write a configuration file
+    FileWrite {
+        /// path of the configuration file
+        path: std::path::PathBuf,
+        /// underlying error
+        source: std::io::Error,
+    },
+    /// Failure to register the SIGHUP handler of the watch, with `cli_settings_watch_sighup`
+    Signal {
+        /// underlying error
+        source: std::io::Error,
+    },
+    /// Failure to parse an environment variable
+    Env {
+        /// name of the environment variable
+        name: String,
+        /// underlying error
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// Failure to read or parse the file containing the value of a secret field
+    SecretFile {
+        /// path of the secret file
+        path: std::path::PathBuf,
+        /// underlying error
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+#[automatically_derived]
+impl ::core::fmt::Debug for SettingsError {
+    #[inline]
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        match self {
+            SettingsError::Help(__self_0) => {
+                ::core::fmt::Formatter::debug_tuple_field1_finish(f, "Help", &__self_0)
+            }
+            SettingsError::Cli(__self_0) => {
+                ::core::fmt::Formatter::debug_tuple_field1_finish(f, "Cli", &__self_0)
+            }
+            SettingsError::FileOpen { path: __self_0, source: __self_1 } => {
+                ::core::fmt::Formatter::debug_struct_field2_finish(
+                    f,
+                    "FileOpen",
+                    "path",
+                    __self_0,
+                    "source",
+                    &__self_1,
+                )
+            }
+            SettingsError::FileFormat { path: __self_0 } => {
+                ::core::fmt::Formatter::debug_struct_field1_finish(
+                    f,
+                    "FileFormat",
+                    "path",
+                    &__self_0,
+                )
+            }
+            SettingsError::FileParse {
+                path: __self_0,
+                line: __self_1,
+                column: __self_2,
+                source: __self_3,
+            } => {
+                ::core::fmt::Formatter::debug_struct_field4_finish(
+                    f,
+                    "FileParse",
+                    "path",
+                    __self_0,
+                    "line",
+                    __self_1,
+                    "column",
+                    __self_2,
+                    "source",
+                    &__self_3,
+                )
+            }
+            SettingsError::IncludeCycle { path: __self_0 } => {
+                ::core::fmt::Formatter::debug_struct_field1_finish(
+                    f,
+                    "IncludeCycle",
+                    "path",
+                    &__self_0,
+                )
+            }
+            SettingsError::Interpolation {
+                path: __self_0,
+                name: __self_1,
+                source: __self_2,
+            } => {
+                ::core::fmt::Formatter::debug_struct_field3_finish(
+                    f,
+                    "Interpolation",
+                    "path",
+                    __self_0,
+                    "name",
+                    __self_1,
+                    "source",
+                    &__self_2,
+                )
+            }
+            SettingsError::UnknownKey {
+                path: __self_0,
+                key: __self_1,
+                suggestion: __self_2,
+            } => {
+                ::core::fmt::Formatter::debug_struct_field3_finish(
+                    f,
+                    "UnknownKey",
+                    "path",
+                    __self_0,
+                    "key",
+                    __self_1,
+                    "suggestion",
+                    &__self_2,
+                )
+            }
+            SettingsError::Validation {
+                field: __self_0,
+                origin: __self_1,
+                source: __self_2,
+            } => {
+                ::core::fmt::Formatter::debug_struct_field3_finish(
+                    f,
+                    "Validation",
+                    "field",
+                    __self_0,
+                    "origin",
+                    __self_1,
+                    "source",
+                    &__self_2,
+                )
+            }
+            SettingsError::InvalidSettings { source: __self_0 } => {
+                ::core::fmt::Formatter::debug_struct_field1_finish(
+                    f,
+                    "InvalidSettings",
+                    "source",
+                    &__self_0,
+                )
+            }
+            SettingsError::Serialize { source: __self_0 } => {
+                ::core::fmt::Formatter::debug_struct_field1_finish(
+                    f,
+                    "Serialize",
+                    "source",
+                    &__self_0,
+                )
+            }
+            SettingsError::FileWrite { path: __self_0, source: __self_1 } => {
+                ::core::fmt::Formatter::debug_struct_field2_finish(
+                    f,
+                    "FileWrite",
+                    "path",
+                    __self_0,
+                    "source",
+                    &__self_1,
+                )
+            }
+            SettingsError::Signal { source: __self_0 } => {
+                ::core::fmt::Formatter::debug_struct_field1_finish(
+                    f,
+                    "Signal",
+                    "source",
+                    &__self_0,
+                )
+            }
+            SettingsError::Env { name: __self_0, source: __self_1 } => {
+                ::core::fmt::Formatter::debug_struct_field2_finish(
+                    f,
+                    "Env",
+                    "name",
+                    __self_0,
+                    "source",
+                    &__self_1,
+                )
+            }
+            SettingsError::SecretFile { path: __self_0, source: __self_1 } => {
+                ::core::fmt::Formatter::debug_struct_field2_finish(
+                    f,
+                    "SecretFile",
+                    "path",
+                    __self_0,
+                    "source",
+                    &__self_1,
+                )
+            }
+        }
+    }
+}
+impl std::fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Help(err) | Self::Cli(err) => err.fmt(f),
+            Self::FileOpen { path, .. } => {
+                f.write_fmt(
+                    format_args!(
+                        "Failed to open the configuration file \'{0}\'", path.display(),
+                    ),
+                )
+            }
+            Self::FileFormat { path } => {
+                f.write_fmt(
+                    format_args!(
+                        "Unsupported format of the configuration file \'{0}\'", path
+                        .display(),
+                    ),
+                )
+            }
+            Self::FileParse { path, line, column, .. } => {
+                f.write_fmt(
+                    format_args!(
+                        "Failed to parse the configuration file \'{0}\'", path.display(),
+                    ),
+                )?;
+                match (line, column) {
+                    (Some(line), Some(column)) => {
+                        f.write_fmt(
+                            format_args!(" at line {0}, column {1}", line, column),
+                        )
+                    }
+                    (Some(line), None) => f.write_fmt(format_args!(" at line {0}", line)),
+                    _ => Ok(()),
+                }
+            }
+            Self::IncludeCycle { path } => {
+                f.write_fmt(
+                    format_args!(
+                        "Include cycle on the configuration file \'{0}\'", path
+                        .display(),
+                    ),
+                )
+            }
+            Self::Interpolation { path, name, .. } => {
+                f.write_fmt(
+                    format_args!(
+                        "Failed to expand \'${{{1}}}\' in the configuration file \'{0}\'",
+                        path.display(), name,
+                    ),
+                )
+            }
+            Self::UnknownKey { path, key, suggestion } => {
+                f.write_fmt(
+                    format_args!(
+                        "Unknown key \'{1}\' in the configuration file \'{0}\'", path
+                        .display(), key,
+                    ),
+                )?;
+                match suggestion {
+                    Some(suggestion) => {
+                        f.write_fmt(format_args!(", did you mean \'{0}\'?", suggestion))
+                    }
+                    None => Ok(()),
+                }
+            }
+            Self::Validation { field, origin, .. } => {
+                f.write_fmt(
+                    format_args!(
+                        "Invalid value of the setting \'{0}\' from the {1}", field,
+                        origin,
+                    ),
+                )
+            }
+            Self::InvalidSettings { .. } => f.write_fmt(format_args!("Invalid settings")),
+            Self::Serialize { .. } => {
+                f.write_fmt(format_args!("Failed to serialize the settings"))
+            }
+            Self::FileWrite { path, .. } => {
+                f.write_fmt(
+                    format_args!(
+                        "Failed to write the configuration file \'{0}\'", path.display(),
+                    ),
+                )
+            }
+            Self::Signal { .. } => {
+                f.write_fmt(format_args!("Failed to register the SIGHUP handler"))
+            }
+            Self::Env { name, .. } => {
+                f.write_fmt(
+                    format_args!(
+                        "Failed to parse the environment variable \'{0}\'", name,
+                    ),
+                )
+            }
+            Self::SecretFile { path, .. } => {
+                f.write_fmt(
+                    format_args!(
+                        "Failed to read the secret file \'{0}\'", path.display(),
+                    ),
+                )
+            }
+        }
+    }
+}
+impl std::error::Error for SettingsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Help(err) | Self::Cli(err) => err.source(),
+            Self::FileOpen { source, .. }
+            | Self::FileWrite { source, .. }
+            | Self::Signal { source } => Some(source),
+            Self::FileFormat { .. }
+            | Self::IncludeCycle { .. }
+            | Self::UnknownKey { .. } => None,
+            Self::FileParse { source, .. }
+            | Self::Validation { source, .. }
+            | Self::InvalidSettings { source, .. }
+            | Self::Serialize { source, .. }
+            | Self::Env { source, .. }
+            | Self::SecretFile { source, .. }
+            | Self::Interpolation { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+/// Source of a setting value
+pub enum SettingsSource {
+    /// Default value
+    Default,
+    /// Configuration file
+    File(std::path::PathBuf),
+    /// Environment variable
+    Env(String),
+    /// Command line argument
+    Cli,
+}
+#[automatically_derived]
+impl ::core::fmt::Debug for SettingsSource {
+    #[inline]
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        match self {
+            SettingsSource::Default => ::core::fmt::Formatter::write_str(f, "Default"),
+            SettingsSource::File(__self_0) => {
+                ::core::fmt::Formatter::debug_tuple_field1_finish(f, "File", &__self_0)
+            }
+            SettingsSource::Env(__self_0) => {
+                ::core::fmt::Formatter::debug_tuple_field1_finish(f, "Env", &__self_0)
+            }
+            SettingsSource::Cli => ::core::fmt::Formatter::write_str(f, "Cli"),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::clone::Clone for SettingsSource {
+    #[inline]
+    fn clone(&self) -> SettingsSource {
+        match self {
+            SettingsSource::Default => SettingsSource::Default,
+            SettingsSource::File(__self_0) => {
+                SettingsSource::File(::core::clone::Clone::clone(__self_0))
+            }
+            SettingsSource::Env(__self_0) => {
+                SettingsSource::Env(::core::clone::Clone::clone(__self_0))
+            }
+            SettingsSource::Cli => SettingsSource::Cli,
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::marker::StructuralPartialEq for SettingsSource {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq for SettingsSource {
+    #[inline]
+    fn eq(&self, other: &SettingsSource) -> bool {
+        let __self_discr = ::core::intrinsics::discriminant_value(self);
+        let __arg1_discr = ::core::intrinsics::discriminant_value(other);
+        __self_discr == __arg1_discr
+            && match (self, other) {
+                (SettingsSource::File(__self_0), SettingsSource::File(__arg1_0)) => {
+                    __self_0 == __arg1_0
+                }
+                (SettingsSource::Env(__self_0), SettingsSource::Env(__arg1_0)) => {
+                    __self_0 == __arg1_0
+                }
+                _ => true,
+            }
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Eq for SettingsSource {
+    #[doc(hidden)]
+    #[coverage(off)]
+    fn assert_fields_are_eq(&self) {
+        let _: ::core::cmp::AssertParamIsEq<std::path::PathBuf>;
+        let _: ::core::cmp::AssertParamIsEq<String>;
+    }
+}
+impl std::fmt::Display for SettingsSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => f.write_fmt(format_args!("default value")),
+            Self::File(path) => {
+                f.write_fmt(format_args!("configuration file \'{0}\'", path.display()))
+            }
+            Self::Env(name) => {
+                f.write_fmt(format_args!("environment variable \'{0}\'", name))
+            }
+            Self::Cli => f.write_fmt(format_args!("command line")),
+        }
+    }
+}
+/// Source of each setting value, returned by `Settings::build_with_sources()`
+pub struct SettingsSources {
+    fields: Vec<(&'static str, SettingsSource)>,
+    unknown_keys: Vec<(std::path::PathBuf, String)>,
+}
+#[automatically_derived]
+impl ::core::fmt::Debug for SettingsSources {
+    #[inline]
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        ::core::fmt::Formatter::debug_struct_field2_finish(
+            f,
+            "SettingsSources",
+            "fields",
+            &self.fields,
+            "unknown_keys",
+            &&self.unknown_keys,
+        )
+    }
+}
+#[automatically_derived]
+impl ::core::clone::Clone for SettingsSources {
+    #[inline]
+    fn clone(&self) -> SettingsSources {
+        SettingsSources {
+            fields: ::core::clone::Clone::clone(&self.fields),
+            unknown_keys: ::core::clone::Clone::clone(&self.unknown_keys),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::marker::StructuralPartialEq for SettingsSources {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq for SettingsSources {
+    #[inline]
+    fn eq(&self, other: &SettingsSources) -> bool {
+        self.fields == other.fields && self.unknown_keys == other.unknown_keys
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Eq for SettingsSources {
+    #[doc(hidden)]
+    #[coverage(off)]
+    fn assert_fields_are_eq(&self) {
+        let _: ::core::cmp::AssertParamIsEq<Vec<(&'static str, SettingsSource)>>;
+        let _: ::core::cmp::AssertParamIsEq<Vec<(std::path::PathBuf, String)>>;
+    }
+}
+impl Default for SettingsSources {
+    fn default() -> Self {
+        Self {
+            fields: ::alloc::boxed::box_assume_init_into_vec_unsafe(
+                ::alloc::intrinsics::write_box_via_move(
+                    ::alloc::boxed::Box::new_uninit(),
+                    [
+                        ("alpha", SettingsSource::Default),
+                        ("beta", SettingsSource::Default),
+                        ("gamma", SettingsSource::Default),
+                        ("path", SettingsSource::Default),
+                    ],
+                ),
+            ),
+            unknown_keys: Vec::new(),
+        }
+    }
+}
+impl SettingsSources {
+    /// Get the source of the value of the given field
+    pub fn get(&self, field: &str) -> Option<&SettingsSource> {
+        self.fields.iter().find(|(name, _)| *name == field).map(|(_, source)| source)
+    }
+    /// Iterate over the fields and the source of their value, in declaration order
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &SettingsSource)> {
+        self.fields.iter().map(|(name, source)| (*name, source))
+    }
+    /// Keys of the configuration files matching no setting, with their file,
+    /// reported with `cli_settings_strict = "warn"`
+    pub fn unknown_keys(&self) -> &[(std::path::PathBuf, String)] {
+        &self.unknown_keys
+    }
+    fn set(&mut self, field: &str, source: SettingsSource) {
+        if let Some(entry) = self.fields.iter_mut().find(|(name, _)| *name == field) {
+            entry.1 = source;
+        }
+    }
+}
+/// Configuration file to load, passed to `Settings::build_with_config_files()`
+pub struct SettingsConfigFile {
+    path: std::path::PathBuf,
+    required: bool,
+}
+#[automatically_derived]
+impl ::core::fmt::Debug for SettingsConfigFile {
+    #[inline]
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        ::core::fmt::Formatter::debug_struct_field2_finish(
+            f,
+            "SettingsConfigFile",
+            "path",
+            &self.path,
+            "required",
+            &&self.required,
+        )
+    }
+}
+#[automatically_derived]
+impl ::core::clone::Clone for SettingsConfigFile {
+    #[inline]
+    fn clone(&self) -> SettingsConfigFile {
+        SettingsConfigFile {
+            path: ::core::clone::Clone::clone(&self.path),
+            required: ::core::clone::Clone::clone(&self.required),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::marker::StructuralPartialEq for SettingsConfigFile {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq for SettingsConfigFile {
+    #[inline]
+    fn eq(&self, other: &SettingsConfigFile) -> bool {
+        self.required == other.required && self.path == other.path
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Eq for SettingsConfigFile {
+    #[doc(hidden)]
+    #[coverage(off)]
+    fn assert_fields_are_eq(&self) {
+        let _: ::core::cmp::AssertParamIsEq<std::path::PathBuf>;
+        let _: ::core::cmp::AssertParamIsEq<bool>;
+    }
+}
+impl SettingsConfigFile {
+    /// Optional configuration file, skipped if it does not exist
+    pub fn optional(path: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            required: false,
+        }
+    }
+    /// Required configuration file, its absence being an error
+    pub fn required(path: impl Into<std::path::PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            required: true,
+        }
+    }
+    /// Path of the configuration file
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+    /// Whether the configuration file is required
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+}
+impl From<std::path::PathBuf> for SettingsConfigFile {
+    fn from(path: std::path::PathBuf) -> Self {
+        Self::optional(path)
+    }
+}
+impl From<&std::path::Path> for SettingsConfigFile {
+    fn from(path: &std::path::Path) -> Self {
+        Self::optional(path)
+    }
+}
+impl From<&std::path::PathBuf> for SettingsConfigFile {
+    fn from(path: &std::path::PathBuf) -> Self {
+        Self::optional(path)
+    }
+}
+impl From<String> for SettingsConfigFile {
+    fn from(path: String) -> Self {
+        Self::optional(path)
+    }
+}
+impl From<&str> for SettingsConfigFile {
+    fn from(path: &str) -> Self {
+        Self::optional(path)
+    }
+}
+/// Configuration file format
+pub enum SettingsFormat {
+    /// YAML format
+    Yaml,
+}
+#[automatically_derived]
+impl ::core::fmt::Debug for SettingsFormat {
+    #[inline]
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+        ::core::fmt::Formatter::write_str(f, "Yaml")
+    }
+}
+#[automatically_derived]
+#[doc(hidden)]
+unsafe impl ::core::clone::TrivialClone for SettingsFormat {}
+#[automatically_derived]
+impl ::core::clone::Clone for SettingsFormat {
+    #[inline]
+    fn clone(&self) -> SettingsFormat {
+        *self
+    }
+}
+#[automatically_derived]
+impl ::core::marker::Copy for SettingsFormat {}
+#[automatically_derived]
+impl ::core::marker::StructuralPartialEq for SettingsFormat {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq for SettingsFormat {
+    #[inline]
+    fn eq(&self, other: &SettingsFormat) -> bool {
+        true
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Eq for SettingsFormat {
+    #[doc(hidden)]
+    #[coverage(off)]
+    fn assert_fields_are_eq(&self) {}
+}
+impl SettingsFormat {
+    /// Get the format of a configuration file from its extension,
+    /// or the default format for unknown extensions
+    pub fn from_path(path: &std::path::Path) -> Option<Self> {
+        match path.extension().and_then(std::ffi::OsStr::to_str) {
+            Some("yml" | "yaml") => Some(Self::Yaml),
+            _ => Some(Self::Yaml),
         }
-        _cli_settings_derive::parse_cli_args(args, &mut cfg)?;
-        Ok(cfg)
     }
 }
 mod _cli_settings_derive {
-    use anyhow::Context;
     use clap::Parser;
     use super::*;
     struct FileSettings {
@@ -47,7 +818,12 @@ mod _cli_settings_derive {
         pub gamma: Option<u64>,
     }
     #[doc(hidden)]
-    #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
+    #[allow(
+        non_upper_case_globals,
+        unused_attributes,
+        unused_qualifications,
+        clippy::absolute_paths,
+    )]
     const _: () = {
         #[allow(unused_extern_crates, clippy::useless_attribute)]
         extern crate serde as _serde;
@@ -55,7 +831,7 @@ mod _cli_settings_derive {
         impl<'de> _serde::Deserialize<'de> for FileSettings {
             fn deserialize<__D>(
                 __deserializer: __D,
-            ) -> _serde::__private::Result<Self, __D::Error>
+            ) -> _serde::__private229::Result<Self, __D::Error>
             where
                 __D: _serde::Deserializer<'de>,
             {
@@ -68,13 +844,14 @@ mod _cli_settings_derive {
                 }
                 #[doc(hidden)]
                 struct __FieldVisitor;
+                #[automatically_derived]
                 impl<'de> _serde::de::Visitor<'de> for __FieldVisitor {
                     type Value = __Field;
                     fn expecting(
                         &self,
-                        __formatter: &mut _serde::__private::Formatter,
-                    ) -> _serde::__private::fmt::Result {
-                        _serde::__private::Formatter::write_str(
+                        __formatter: &mut _serde::__private229::Formatter,
+                    ) -> _serde::__private229::fmt::Result {
+                        _serde::__private229::Formatter::write_str(
                             __formatter,
                             "field identifier",
                         )
@@ -82,48 +859,49 @@ mod _cli_settings_derive {
                     fn visit_u64<__E>(
                         self,
                         __value: u64,
-                    ) -> _serde::__private::Result<Self::Value, __E>
+                    ) -> _serde::__private229::Result<Self::Value, __E>
                     where
                         __E: _serde::de::Error,
                     {
                         match __value {
-                            0u64 => _serde::__private::Ok(__Field::__field0),
-                            1u64 => _serde::__private::Ok(__Field::__field1),
-                            _ => _serde::__private::Ok(__Field::__ignore),
+                            0u64 => _serde::__private229::Ok(__Field::__field0),
+                            1u64 => _serde::__private229::Ok(__Field::__field1),
+                            _ => _serde::__private229::Ok(__Field::__ignore),
                         }
                     }
                     fn visit_str<__E>(
                         self,
                         __value: &str,
-                    ) -> _serde::__private::Result<Self::Value, __E>
+                    ) -> _serde::__private229::Result<Self::Value, __E>
                     where
                         __E: _serde::de::Error,
                     {
                         match __value {
-                            "alpha" => _serde::__private::Ok(__Field::__field0),
-                            "gamma" => _serde::__private::Ok(__Field::__field1),
-                            _ => _serde::__private::Ok(__Field::__ignore),
+                            "alpha" => _serde::__private229::Ok(__Field::__field0),
+                            "gamma" => _serde::__private229::Ok(__Field::__field1),
+                            _ => _serde::__private229::Ok(__Field::__ignore),
                         }
                     }
                     fn visit_bytes<__E>(
                         self,
                         __value: &[u8],
-                    ) -> _serde::__private::Result<Self::Value, __E>
+                    ) -> _serde::__private229::Result<Self::Value, __E>
                     where
                         __E: _serde::de::Error,
                     {
                         match __value {
-                            b"alpha" => _serde::__private::Ok(__Field::__field0),
-                            b"gamma" => _serde::__private::Ok(__Field::__field1),
-                            _ => _serde::__private::Ok(__Field::__ignore),
+                            b"alpha" => _serde::__private229::Ok(__Field::__field0),
+                            b"gamma" => _serde::__private229::Ok(__Field::__field1),
+                            _ => _serde::__private229::Ok(__Field::__ignore),
                         }
                     }
                 }
+                #[automatically_derived]
                 impl<'de> _serde::Deserialize<'de> for __Field {
                     #[inline]
                     fn deserialize<__D>(
                         __deserializer: __D,
-                    ) -> _serde::__private::Result<Self, __D::Error>
+                    ) -> _serde::__private229::Result<Self, __D::Error>
                     where
                         __D: _serde::Deserializer<'de>,
                     {
@@ -135,16 +913,17 @@ mod _cli_settings_derive {
                 }
                 #[doc(hidden)]
                 struct __Visitor<'de> {
-                    marker: _serde::__private::PhantomData<FileSettings>,
-                    lifetime: _serde::__private::PhantomData<&'de ()>,
+                    marker: _serde::__private229::PhantomData<FileSettings>,
+                    lifetime: _serde::__private229::PhantomData<&'de ()>,
                 }
+                #[automatically_derived]
                 impl<'de> _serde::de::Visitor<'de> for __Visitor<'de> {
                     type Value = FileSettings;
                     fn expecting(
                         &self,
-                        __formatter: &mut _serde::__private::Formatter,
-                    ) -> _serde::__private::fmt::Result {
-                        _serde::__private::Formatter::write_str(
+                        __formatter: &mut _serde::__private229::Formatter,
+                    ) -> _serde::__private229::fmt::Result {
+                        _serde::__private229::Formatter::write_str(
                             __formatter,
                             "struct FileSettings",
                         )
@@ -153,16 +932,16 @@ mod _cli_settings_derive {
                     fn visit_seq<__A>(
                         self,
                         mut __seq: __A,
-                    ) -> _serde::__private::Result<Self::Value, __A::Error>
+                    ) -> _serde::__private229::Result<Self::Value, __A::Error>
                     where
                         __A: _serde::de::SeqAccess<'de>,
                     {
                         let __field0 = match _serde::de::SeqAccess::next_element::<
                             Option<u32>,
                         >(&mut __seq)? {
-                            _serde::__private::Some(__value) => __value,
-                            _serde::__private::None => {
-                                return _serde::__private::Err(
+                            _serde::__private229::Some(__value) => __value,
+                            _serde::__private229::None => {
+                                return _serde::__private229::Err(
                                     _serde::de::Error::invalid_length(
                                         0usize,
                                         &"struct FileSettings with 2 elements",
@@ -173,9 +952,9 @@ mod _cli_settings_derive {
                         let __field1 = match _serde::de::SeqAccess::next_element::<
                             Option<u64>,
                         >(&mut __seq)? {
-                            _serde::__private::Some(__value) => __value,
-                            _serde::__private::None => {
-                                return _serde::__private::Err(
+                            _serde::__private229::Some(__value) => __value,
+                            _serde::__private229::None => {
+                                return _serde::__private229::Err(
                                     _serde::de::Error::invalid_length(
                                         1usize,
                                         &"struct FileSettings with 2 elements",
@@ -183,7 +962,7 @@ mod _cli_settings_derive {
                                 );
                             }
                         };
-                        _serde::__private::Ok(FileSettings {
+                        _serde::__private229::Ok(FileSettings {
                             alpha: __field0,
                             gamma: __field1,
                         })
@@ -192,35 +971,35 @@ mod _cli_settings_derive {
                     fn visit_map<__A>(
                         self,
                         mut __map: __A,
-                    ) -> _serde::__private::Result<Self::Value, __A::Error>
+                    ) -> _serde::__private229::Result<Self::Value, __A::Error>
                     where
                         __A: _serde::de::MapAccess<'de>,
                     {
-                        let mut __field0: _serde::__private::Option<Option<u32>> = _serde::__private::None;
-                        let mut __field1: _serde::__private::Option<Option<u64>> = _serde::__private::None;
-                        while let _serde::__private::Some(__key) = _serde::de::MapAccess::next_key::<
+                        let mut __field0: _serde::__private229::Option<Option<u32>> = _serde::__private229::None;
+                        let mut __field1: _serde::__private229::Option<Option<u64>> = _serde::__private229::None;
+                        while let _serde::__private229::Some(__key) = _serde::de::MapAccess::next_key::<
                             __Field,
                         >(&mut __map)? {
                             match __key {
                                 __Field::__field0 => {
-                                    if _serde::__private::Option::is_some(&__field0) {
-                                        return _serde::__private::Err(
+                                    if _serde::__private229::Option::is_some(&__field0) {
+                                        return _serde::__private229::Err(
                                             <__A::Error as _serde::de::Error>::duplicate_field("alpha"),
                                         );
                                     }
-                                    __field0 = _serde::__private::Some(
+                                    __field0 = _serde::__private229::Some(
                                         _serde::de::MapAccess::next_value::<
                                             Option<u32>,
                                         >(&mut __map)?,
                                     );
                                 }
                                 __Field::__field1 => {
-                                    if _serde::__private::Option::is_some(&__field1) {
-                                        return _serde::__private::Err(
+                                    if _serde::__private229::Option::is_some(&__field1) {
+                                        return _serde::__private229::Err(
                                             <__A::Error as _serde::de::Error>::duplicate_field("gamma"),
                                         );
                                     }
-                                    __field1 = _serde::__private::Some(
+                                    __field1 = _serde::__private229::Some(
                                         _serde::de::MapAccess::next_value::<
                                             Option<u64>,
                                         >(&mut __map)?,
@@ -234,18 +1013,18 @@ mod _cli_settings_derive {
                             }
                         }
                         let __field0 = match __field0 {
-                            _serde::__private::Some(__field0) => __field0,
-                            _serde::__private::None => {
-                                _serde::__private::de::missing_field("alpha")?
+                            _serde::__private229::Some(__field0) => __field0,
+                            _serde::__private229::None => {
+                                _serde::__private229::de::missing_field("alpha")?
                             }
                         };
                         let __field1 = match __field1 {
-                            _serde::__private::Some(__field1) => __field1,
-                            _serde::__private::None => {
-                                _serde::__private::de::missing_field("gamma")?
+                            _serde::__private229::Some(__field1) => __field1,
+                            _serde::__private229::None => {
+                                _serde::__private229::de::missing_field("gamma")?
                             }
                         };
-                        _serde::__private::Ok(FileSettings {
+                        _serde::__private229::Ok(FileSettings {
                             alpha: __field0,
                             gamma: __field1,
                         })
@@ -258,60 +1037,152 @@ mod _cli_settings_derive {
                     "FileSettings",
                     FIELDS,
                     __Visitor {
-                        marker: _serde::__private::PhantomData::<FileSettings>,
-                        lifetime: _serde::__private::PhantomData,
+                        marker: _serde::__private229::PhantomData::<FileSettings>,
+                        lifetime: _serde::__private229::PhantomData,
                     },
                 )
             }
         }
     };
     impl FileSettings {
-        fn update(self, cfg: &mut super::Settings) {
+        fn update(
+            self,
+            cfg: &mut super::Settings,
+            sources: &mut super::SettingsSources,
+            source: &super::SettingsSource,
+        ) {
             if let Some(param) = self.alpha {
                 cfg.alpha = param;
+                sources.set("alpha", source.clone());
             }
             if let Some(param) = self.gamma {
                 cfg.gamma = param;
+                sources.set("gamma", source.clone());
             }
         }
     }
     pub fn load_file(
-        path: &std::path::Path,
+        file: &super::SettingsConfigFile,
         cfg: &mut super::Settings,
-    ) -> anyhow::Result<()> {
-        let file = std::fs::File::open(path);
-        if let Err(err) = file {
-            if err.kind() == std::io::ErrorKind::NotFound {
+        sources: &mut super::SettingsSources,
+    ) -> Result<(), super::SettingsError> {
+        let path = file.path.as_path();
+        if path.is_dir() {
+            for file in dir_files(path)? {
+                load_file(&file, cfg, sources)?;
+            }
+            return Ok(());
+        }
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound && !file.required => {
                 return Ok(());
             }
-            return Err(err)
-                .context(
-                    ::alloc::__export::must_use({
-                        let res = ::alloc::fmt::format(
+            Err(err) => {
+                return Err(super::SettingsError::FileOpen {
+                    path: path.to_path_buf(),
+                    source: err,
+                });
+            }
+        };
+        let file_config: FileSettings = parse_file(path, &content)?;
+        file_config
+            .update(cfg, sources, &super::SettingsSource::File(path.to_path_buf()));
+        Ok(())
+    }
+    /// Get the configuration files of a directory, in lexical order
+    fn dir_files(
+        path: &std::path::Path,
+    ) -> Result<Vec<super::SettingsConfigFile>, super::SettingsError> {
+        const EXTENSIONS: &[&str] = &["yml", "yaml"];
+        let entries = std::fs::read_dir(path)
+            .map_err(|err| super::SettingsError::FileOpen {
+                path: path.to_path_buf(),
+                source: err,
+            })?;
+        let mut files = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && !path
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+                    && path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| EXTENSIONS.contains(&ext))
+            })
+            .collect::<Vec<_>>();
+        files.sort();
+        Ok(files.into_iter().map(super::SettingsConfigFile::optional).collect())
+    }
+    fn parse_file<T>(
+        path: &std::path::Path,
+        content: &str,
+    ) -> Result<T, super::SettingsError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match super::SettingsFormat::from_path(path) {
+            Some(format) => parse_format(path, content, format),
+            None => {
+                Err(super::SettingsError::FileFormat {
+                    path: path.to_path_buf(),
+                })
+            }
+        }
+    }
+    fn parse_format<T>(
+        path: &std::path::Path,
+        content: &str,
+        format: super::SettingsFormat,
+    ) -> Result<T, super::SettingsError>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match format {
+            super::SettingsFormat::Yaml => {
+                serde_yaml::from_str(content)
+                    .map_err(|err| {
+                        let location = err.location().map(|l| (l.line(), l.column()));
+                        parse_error(path, location, err)
+                    })
+            }
+        }
+    }
+    fn parse_error<E>(
+        path: &std::path::Path,
+        location: Option<(usize, usize)>,
+        err: E,
+    ) -> super::SettingsError
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let message = err.to_string();
+        if let Some(start) = message.find("invalid value of the secret field '") {
+            let rest = &message[start + "invalid value of the secret field '".len()..];
+            let key = rest.split('\'').next().unwrap_or_default();
+            return super::SettingsError::FileParse {
+                path: path.to_path_buf(),
+                line: None,
+                column: None,
+                source: ::alloc::__export::must_use({
+                        ::alloc::fmt::format(
                             format_args!(
-                                "Failed to open the configuration file \'{0}\'", path
-                                .display(),
+                                "{0}{1}\'", "invalid value of the secret field '", key,
                             ),
-                        );
-                        res
-                    }),
-                );
-        }
-        let file = file.unwrap();
-        let file_config: FileSettings = serde_yaml::from_reader(file)
-            .with_context(|| {
-                ::alloc::__export::must_use({
-                    let res = ::alloc::fmt::format(
-                        format_args!(
-                            "Failed to parse the configuration file \'{0}\'", path
-                            .display(),
-                        ),
-                    );
-                    res
-                })
-            })?;
-        file_config.update(cfg);
-        Ok(())
+                        )
+                    })
+                    .into(),
+            };
+        }
+        super::SettingsError::FileParse {
+            path: path.to_path_buf(),
+            line: location.map(|(line, _)| line),
+            column: location.map(|(_, column)| column),
+            source: Box::new(err),
+        }
     }
     /// Application summary (visible with -h)
     ///
@@ -353,11 +1224,17 @@ mod _cli_settings_derive {
     #[automatically_derived]
     impl clap::CommandFactory for ClapSettings {
         fn command<'b>() -> clap::Command {
-            let __clap_app = clap::Command::new("cli-settings-derive-tests");
+            let __clap_app = clap::Command::new({
+                let _ = "cli-settings-derive-tests";
+                "cli-settings-derive-tests"
+            });
             <Self as clap::Args>::augment_args(__clap_app)
         }
         fn command_for_update<'b>() -> clap::Command {
-            let __clap_app = clap::Command::new("cli-settings-derive-tests");
+            let __clap_app = clap::Command::new({
+                let _ = "cli-settings-derive-tests";
+                "cli-settings-derive-tests"
+            });
             <Self as clap::Args>::augment_args_for_update(__clap_app)
         }
     }
@@ -399,7 +1276,7 @@ mod _cli_settings_derive {
                     .remove_one::<std::path::PathBuf>("path")
                     .ok_or_else(|| clap::Error::raw(
                         clap::error::ErrorKind::MissingRequiredArgument,
-                        "The following required argument was not provided: path",
+                        "the following required argument was not provided: path",
                     ))?,
             };
             ::std::result::Result::Ok(v)
@@ -432,7 +1309,7 @@ mod _cli_settings_derive {
                     .remove_one::<std::path::PathBuf>("path")
                     .ok_or_else(|| clap::Error::raw(
                         clap::error::ErrorKind::MissingRequiredArgument,
-                        "The following required argument was not provided: path",
+                        "the following required argument was not provided: path",
                     ))?;
             }
             ::std::result::Result::Ok(())
@@ -464,7 +1341,8 @@ mod _cli_settings_derive {
             Some(clap::Id::from("ClapSettings"))
         }
         fn augment_args<'b>(__clap_app: clap::Command) -> clap::Command {
-            {
+            let __clap_app = __clap_app;
+            let __clap_app = {
                 let __clap_app = __clap_app
                     .group(
                         clap::ArgGroup::new("ClapSettings")
@@ -538,15 +1416,20 @@ mod _cli_settings_derive {
                         arg
                     });
                 __clap_app
-                    .about("Application summary (visible with -h)")
-                    .long_about(
-                        "Application summary (visible with -h)\n\nApplication long description (visible with --help)",
-                    )
-                    .version("0.0.0")
-            }
+            };
+            __clap_app
+                .about("Application summary (visible with -h)")
+                .long_about(
+                    "Application summary (visible with -h)\n\nApplication long description (visible with --help)",
+                )
+                .version({
+                    let _ = "0.0.0";
+                    "0.0.0"
+                })
         }
         fn augment_args_for_update<'b>(__clap_app: clap::Command) -> clap::Command {
-            {
+            let __clap_app = __clap_app;
+            let __clap_app = {
                 let __clap_app = __clap_app
                     .group(
                         clap::ArgGroup::new("ClapSettings")
@@ -620,32 +1503,55 @@ mod _cli_settings_derive {
                         arg
                     });
                 __clap_app
-                    .about("Application summary (visible with -h)")
-                    .long_about(
-                        "Application summary (visible with -h)\n\nApplication long description (visible with --help)",
-                    )
-                    .version("0.0.0")
-            }
+            };
+            __clap_app
+                .about("Application summary (visible with -h)")
+                .long_about(
+                    "Application summary (visible with -h)\n\nApplication long description (visible with --help)",
+                )
+                .version({
+                    let _ = "0.0.0";
+                    "0.0.0"
+                })
         }
     }
     impl ClapSettings {
-        fn update(self, cfg: &mut super::Settings) {
+        fn update(
+            self,
+            cfg: &mut super::Settings,
+            sources: &mut super::SettingsSources,
+            source: &super::SettingsSource,
+        ) {
             if let Some(param) = self.alpha {
                 cfg.alpha = param;
+                sources.set("alpha", source.clone());
             }
             if let Some(param) = self.beta {
                 cfg.beta = param;
+                sources.set("beta", source.clone());
             }
             cfg.path = self.path;
+            sources.set("path", source.clone());
         }
     }
-    pub fn parse_cli_args<I, T>(args: I, cfg: &mut super::Settings) -> anyhow::Result<()>
+    pub fn parse_cli_args<I, T>(
+        args: I,
+        cfg: &mut super::Settings,
+        sources: &mut super::SettingsSources,
+    ) -> Result<(), super::SettingsError>
     where
         I: IntoIterator<Item = T>,
         T: Into<std::ffi::OsString> + Clone,
     {
-        let cli_args = ClapSettings::parse_from(args);
-        cli_args.update(cfg);
+        let cli_args = ClapSettings::try_parse_from(args).map_err(cli_error)?;
+        cli_args.update(cfg, sources, &super::SettingsSource::Cli);
         Ok(())
     }
+    fn cli_error(err: clap::Error) -> super::SettingsError {
+        match err.kind() {
+            clap::error::ErrorKind::DisplayHelp
+            | clap::error::ErrorKind::DisplayVersion => super::SettingsError::Help(err),
+            _ => super::SettingsError::Cli(err),
+        }
+    }
 }
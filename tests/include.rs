@@ -0,0 +1,75 @@
+//! Test the configuration files including other configuration files
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_include]
+#[cli_settings_strict]
+pub struct Settings {
+    /// alpha setting explanation
+    #[cli_settings_file]
+    pub alpha: u32,
+
+    /// beta setting explanation
+    #[cli_settings_file]
+    pub beta: u32,
+
+    /// gamma setting explanation
+    #[cli_settings_file]
+    pub gamma: u32,
+
+    /// delta setting explanation
+    #[cli_settings_file]
+    pub delta: u32,
+}
+
+/// Test the included files, loaded before the including file
+#[test]
+pub fn build() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let include_dir = std::path::Path::new(&project_dir).join("tests/include");
+
+    let (cfg, sources) =
+        Settings::build_with_sources(vec![include_dir.join("main.yml")], ["test-bin"])?;
+    assert_eq!(
+        cfg,
+        Settings {
+            alpha: 10,
+            beta: 2,
+            gamma: 3,
+            delta: 4,
+        }
+    );
+    assert_eq!(
+        sources.get("beta"),
+        Some(&SettingsSource::File(include_dir.join("conf.d/10-first.yml")))
+    );
+    Ok(())
+}
+
+/// Test the include errors
+#[test]
+pub fn include_error() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let include_dir = std::path::Path::new(&project_dir).join("tests/include");
+
+    let err = Settings::try_build(vec![include_dir.join("cycle1.yml")], ["test-bin"]).unwrap_err();
+    assert!(
+        matches!(err, SettingsError::IncludeCycle { ref path } if *path == include_dir.join("cycle1.yml")),
+        "{err}"
+    );
+
+    let err = Settings::try_build(vec![include_dir.join("missing.yml")], ["test-bin"]).unwrap_err();
+    assert!(
+        matches!(err, SettingsError::FileOpen { ref path, .. } if *path == include_dir.join("does-not-exist.yml")),
+        "{err}"
+    );
+    Ok(())
+}
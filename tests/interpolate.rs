@@ -0,0 +1,140 @@
+//! Test the expansion of the references in the values of the configuration files
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[derive(serde::Deserialize, serde::Serialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_interpolate]
+#[cli_settings_serialize]
+pub struct Settings {
+    /// application name
+    #[cli_settings_default = "\"app\".to_string()"]
+    #[cli_settings_file]
+    pub name: String,
+
+    /// data directory
+    #[cli_settings_file]
+    pub data_dir: std::path::PathBuf,
+
+    /// log file
+    #[cli_settings_file]
+    pub log_file: String,
+
+    /// backup of the log file
+    #[cli_settings_file]
+    pub backup_file: String,
+
+    /// listening port
+    #[cli_settings_file]
+    pub port: u16,
+
+    /// public URL
+    #[cli_settings_file]
+    pub url: String,
+
+    /// price, with escaped dollars
+    #[cli_settings_file]
+    pub price: String,
+
+    /// tags
+    #[cli_settings_file]
+    pub tags: Vec<String>,
+}
+
+/// Test the references to the environment variables, to the values of the previous files, of the file itself
+/// and to the default values, for string and non string fields
+#[test]
+pub fn interpolate() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let tests_dir = std::path::Path::new(&project_dir).join("tests");
+    std::env::set_var("CLI_SETTINGS_TEST_INTERPOLATE_HOME", "/home/user");
+    std::env::set_var("CLI_SETTINGS_TEST_INTERPOLATE_PORT", "8080");
+
+    let cfg = Settings::try_build(
+        vec![
+            tests_dir.join("interpolate1.yml"),
+            tests_dir.join("interpolate2.yml"),
+        ],
+        ["test-bin"],
+    )?;
+    assert_eq!(
+        cfg,
+        Settings {
+            name: "app".to_string(),
+            data_dir: "/home/user/data".into(),
+            log_file: "/home/user/data/app.log".to_string(),
+            backup_file: "/home/user/data/app.log.1".to_string(),
+            port: 8080,
+            url: "http://localhost:8080/".to_string(),
+            price: "$5 for $HOME".to_string(),
+            tags: vec!["8080".to_string(), "plain".to_string()],
+        }
+    );
+    Ok(())
+}
+
+/// Test the references that cannot be expanded
+#[test]
+pub fn interpolate_error() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("interpolate");
+    std::fs::create_dir_all(&dir)?;
+    let file = dir.join("error.yml");
+
+    for (content, name, reason) in [
+        (
+            "log_file: ${CLI_SETTINGS_TEST_UNDEFINED}/app.log\n",
+            "CLI_SETTINGS_TEST_UNDEFINED",
+            "undefined variable",
+        ),
+        (
+            "log_file: ${url}\nurl: ${log_file}\n",
+            "url",
+            "recursive reference",
+        ),
+        (
+            "log_file: ${data_dir\nurl: x}\n",
+            "data_dir",
+            "unterminated reference",
+        ),
+    ] {
+        std::fs::write(&file, content)?;
+        let err = Settings::try_build(vec![file.clone()], ["test-bin"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Failed to expand '${{{name}}}' in the configuration file '{}'",
+                file.display()
+            )
+        );
+        assert_eq!(
+            std::error::Error::source(&err).map(ToString::to_string),
+            Some(reason.to_string())
+        );
+    }
+    Ok(())
+}
+
+/// Test the serialized settings, whose dollars are escaped to be loaded back as is
+#[test]
+pub fn interpolate_serialize() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("interpolate");
+    std::fs::create_dir_all(&dir)?;
+    let file = dir.join("serialize.yml");
+
+    let cfg = Settings {
+        price: "$5 for ${HOME}".to_string(),
+        tags: vec!["$$".to_string()],
+        ..Settings::default()
+    };
+    let text = cfg.to_file_string(SettingsFormat::Yaml)?;
+    assert!(text.contains("price: $$5 for $${HOME}\n"), "{text}");
+    cfg.write_config(&file)?;
+    assert_eq!(Settings::try_build(vec![file], ["test-bin"])?, cfg);
+    Ok(())
+}
@@ -0,0 +1,79 @@
+//! Test usage of `cli_settings` with JSON and JSON5 configuration files
+
+#![cfg(all(feature = "json", feature = "json5"))]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[serde_with::serde_as]#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_format = "none"]
+pub struct Settings {
+    /// gamma setting explanation
+    #[cli_settings_default = "1 << 63"]
+    #[cli_settings_file]
+    pub gamma: u64,
+
+    /// delta setting explanation
+    #[cli_settings_default = "42"]
+    #[cli_settings_file]
+    pub delta: u32,
+
+    /// epsilon setting explanation
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(short, long)]"]
+    pub epsilon: u32,
+}
+
+/// Test `build()` method
+/// Mix JSON and JSON5 files, the format being selected from the file extension
+#[test]
+pub fn build() -> anyhow::Result<()> {
+    let expected = Settings {
+        gamma: 728,   // set by JSON file
+        delta: 32,    // set by JSON and JSON5 files
+        epsilon: 512, // set by JSON5 file
+    };
+
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let test_dir = std::path::Path::new(&project_dir).join("tests");
+
+    let cfg = Settings::build(
+        vec![test_dir.join("usage.json"), test_dir.join("usage.json5")],
+        ["test-bin"],
+    )?;
+    assert_eq!(cfg, expected);
+    Ok(())
+}
+
+/// Test that files with an unknown extension are rejected without default format
+#[test]
+pub fn unknown_extension() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("json");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("config.cfg");
+    std::fs::write(&path, "{}\n")?;
+
+    let err = Settings::build(vec![path.clone()], ["test-bin"]).unwrap_err();
+    assert!(matches!(err, SettingsError::FileFormat { path: p } if p == path));
+    Ok(())
+}
+
+/// Test the location of parse errors
+#[test]
+pub fn parse_error() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("json");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("invalid.json");
+    std::fs::write(&path, "{\n  \"delta\": \"text\"\n}\n")?;
+
+    let err = Settings::build(vec![path.clone()], ["test-bin"]).unwrap_err();
+    assert!(
+        matches!(err, SettingsError::FileParse { path: ref p, line: Some(2), .. } if *p == path),
+        "{err}"
+    );
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//! Test merge strategies of collection fields set by several sources
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+use std::collections::HashMap;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+pub struct Settings {
+    /// plugins to load, from all sources
+    #[cli_settings_default = "vec![\"builtin\".to_string()]"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long = \"plugin\")]"]
+    #[cli_settings_merge = "append"]
+    pub plugins: Vec<String>,
+
+    /// search paths, the last source first
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long = \"path\")]"]
+    #[cli_settings_merge = "prepend"]
+    pub paths: Vec<String>,
+
+    /// tags, without duplicates
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long = \"tag\")]"]
+    #[cli_settings_merge = "union"]
+    pub tags: Vec<String>,
+
+    /// labels, merged by key
+    #[cli_settings_file]
+    #[cli_settings_merge = "append"]
+    pub labels: HashMap<String, String>,
+
+    /// command aliases, merged key by key
+    #[cli_settings_file]
+    #[cli_settings_merge = "deep"]
+    pub aliases: HashMap<String, String>,
+
+    /// excluded patterns, from the last source only
+    #[cli_settings_default = "vec![\"*.bak\".to_string()]"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long = \"exclude\")]"]
+    #[cli_settings_merge = "replace"]
+    pub excludes: Vec<String>,
+}
+
+/// Test `build()` method
+/// Collection fields are merged across the files and the command line arguments
+#[test]
+pub fn build() -> anyhow::Result<()> {
+    let expected = Settings {
+        plugins: vec![
+            "builtin".to_string(),
+            "system".to_string(),
+            "user".to_string(),
+            "cli1".to_string(),
+            "cli2".to_string(),
+        ],
+        paths: vec![
+            "/opt/lib".to_string(),
+            "/home/user/lib".to_string(),
+            "/usr/lib".to_string(),
+        ],
+        tags: vec![
+            "a".to_string(),
+            "b".to_string(),
+            "c".to_string(),
+            "d".to_string(),
+        ],
+        labels: HashMap::from([
+            ("env".to_string(), "prod".to_string()),
+            ("zone".to_string(), "us".to_string()),
+        ]),
+        aliases: HashMap::from([
+            ("ll".to_string(), "ls -lh".to_string()),
+            ("g".to_string(), "git".to_string()),
+        ]),
+        excludes: vec!["tmp".to_string()],
+    };
+
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let test_dir = std::path::Path::new(&project_dir).join("tests");
+
+    let cfg = Settings::build(
+        vec![test_dir.join("merge1.yml"), test_dir.join("merge2.yml")],
+        [
+            "test-bin",
+            "--plugin=cli1",
+            "--plugin=cli2",
+            "--path=/opt/lib",
+            "--tag=c",
+            "--tag=d",
+            "--tag=d",
+        ],
+    )?;
+    assert_eq!(cfg, expected);
+    Ok(())
+}
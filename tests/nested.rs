@@ -0,0 +1,165 @@
+//! Test nested settings structs, merged field by field
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug, Clone)]
+#[cli_settings]
+#[cli_settings_nested = "db"]
+#[cli_settings_file = "#[derive(serde::Deserialize, serde::Serialize)]"]
+#[cli_settings_clap = "#[derive(clap::Args)]"]
+#[cli_settings_serialize]
+pub struct Database {
+    /// database host
+    #[cli_settings_default = "\"localhost\".to_string()"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub host: String,
+
+    /// database port
+    #[cli_settings_default = "5432"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub port: u16,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+#[cli_settings]
+#[cli_settings_nested = "http"]
+#[cli_settings_file = "#[derive(serde::Deserialize, serde::Serialize)]"]
+#[cli_settings_clap = "#[derive(clap::Args)]"]
+#[cli_settings_serialize]
+pub struct Http {
+    /// listening address
+    #[cli_settings_default = "\"127.0.0.1\".to_string()"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub host: String,
+
+    /// listening port
+    #[cli_settings_default = "80"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long = \"listen\")]"]
+    pub port: u16,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+#[cli_settings]
+#[cli_settings_nested]
+#[cli_settings_file = "#[derive(serde::Deserialize, serde::Serialize)]"]
+#[cli_settings_clap = "#[derive(clap::Args)]"]
+#[cli_settings_serialize]
+pub struct Logging {
+    /// log level
+    #[cli_settings_default = "\"info\".to_string()"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub level: String,
+}
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[derive(serde::Deserialize, serde::Serialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_serialize]
+pub struct Settings {
+    /// database settings
+    #[cli_settings_nested]
+    #[cli_settings_file]
+    #[cli_settings_clap]
+    pub database: Database,
+
+    /// http server settings
+    #[cli_settings_nested]
+    #[cli_settings_file]
+    #[cli_settings_clap]
+    pub http: Http,
+
+    /// logging settings, only from configuration files
+    #[cli_settings_nested]
+    #[cli_settings_file]
+    pub logging: Logging,
+
+    /// number of workers
+    #[cli_settings_default = "1"]
+    #[cli_settings_clap = "#[arg(short, long)]"]
+    pub workers: u32,
+}
+
+/// Test `build()` method
+/// Sections are merged key by key across the files, then updated by the prefixed command line arguments
+#[test]
+pub fn build() -> anyhow::Result<()> {
+    let expected = Settings {
+        database: Database {
+            host: "db.example.com".to_string(), // set by first config file
+            port: 6543,                         // set by second config file
+        },
+        http: Http {
+            host: "0.0.0.0".to_string(), // set by second config file
+            port: 8080,                  // set by command line
+        },
+        logging: Logging {
+            level: "debug".to_string(), // set by first config file
+        },
+        workers: 4,
+    };
+
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let test_dir = std::path::Path::new(&project_dir).join("tests");
+
+    let (cfg, sources) = Settings::build_with_sources(
+        vec![test_dir.join("nested1.yml"), test_dir.join("nested2.yml")],
+        ["test-bin", "--http-listen", "8080", "-w", "4"],
+    )?;
+    assert_eq!(cfg, expected);
+    assert_eq!(
+        sources.get("database"),
+        Some(&SettingsSource::File(test_dir.join("nested2.yml")))
+    );
+    assert_eq!(sources.get("http"), Some(&SettingsSource::Cli));
+    assert_eq!(
+        sources.get("logging"),
+        Some(&SettingsSource::File(test_dir.join("nested1.yml")))
+    );
+    Ok(())
+}
+
+/// Test the prefixed command line arguments
+#[test]
+pub fn cli_prefix() -> anyhow::Result<()> {
+    let cfg = Settings::try_build(
+        Vec::new(),
+        [
+            "test-bin",
+            "--db-host",
+            "db",
+            "--db-port=1234",
+            "--http-host",
+            "::",
+        ],
+    )?;
+    assert_eq!(cfg.database.host, "db");
+    assert_eq!(cfg.database.port, 1234);
+    assert_eq!(cfg.http.host, "::");
+    assert_eq!(cfg.http.port, 80);
+    assert!(matches!(
+        Settings::try_build(Vec::new(), ["test-bin", "--host", "db"]),
+        Err(SettingsError::Cli(_))
+    ));
+    Ok(())
+}
+
+/// Test `to_file_string()` method with nested structs
+#[test]
+pub fn to_file_string() -> anyhow::Result<()> {
+    let cfg = Settings::default();
+    assert_eq!(
+        cfg.to_file_string(SettingsFormat::Yaml)?,
+        "database:\n  host: localhost\n  port: 5432\nhttp:\n  host: 127.0.0.1\n  port: 80\nlogging:\n  level: info\n"
+    );
+    Ok(())
+}
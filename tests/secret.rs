@@ -0,0 +1,195 @@
+//! Test the secret fields, never output by the generated code
+
+#![cfg(all(feature = "yaml", feature = "env"))]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[cli_settings]
+#[derive(PartialEq, Clone)]
+#[cli_settings_file = "#[derive(serde::Deserialize, serde::Serialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_serialize]
+#[cli_settings_diff]
+pub struct Settings {
+    /// user setting explanation
+    #[cli_settings_default = "\"admin\".to_string()"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub user: String,
+
+    /// password setting explanation
+    #[cli_settings_default = "\"changeme\".to_string()"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    #[cli_settings_secret]
+    pub password: String,
+
+    /// pin setting explanation
+    #[cli_settings_file]
+    #[cli_settings_env = "CLI_SETTINGS_TEST_SECRET_PIN"]
+    #[cli_settings_secret]
+    pub pin: u32,
+}
+
+/// Test the secret fields, loaded as usual but masked or omitted in the outputs
+#[test]
+pub fn secret() -> anyhow::Result<()> {
+    let cfg = Settings::try_build(Vec::new(), ["test-bin", "--password", "hunter2"])?;
+    assert_eq!(cfg.password, "hunter2");
+
+    // debug output
+    assert_eq!(
+        format!("{cfg:?}"),
+        "Settings { user: \"admin\", password: ***, pin: *** }"
+    );
+
+    // configuration dumps
+    let format = SettingsFormat::Yaml;
+    assert_eq!(cfg.to_file_string(format)?, "user: admin\n");
+    let sample = Settings::sample_config(format)?;
+    assert!(!sample.contains("password"), "{sample}");
+    assert!(!sample.contains("changeme"), "{sample}");
+
+    // comparison
+    let other = Settings {
+        password: "hunter3".to_string(),
+        ..cfg.clone()
+    };
+    assert_eq!(
+        cfg.diff(&other),
+        vec![SettingsFieldChange {
+            field: "password".to_string(),
+            old: "***".to_string(),
+            new: "***".to_string(),
+        }]
+    );
+    Ok(())
+}
+
+/// Test the secret values read from files, given in a configuration file, on the command line or in the
+/// environment
+#[test]
+pub fn secret_file() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("secret");
+    std::fs::create_dir_all(&dir)?;
+    let password = dir.join("password");
+    std::fs::write(&password, "hunter2\n")?;
+    let pin = dir.join("pin");
+    std::fs::write(&pin, "1234\n")?;
+    let invalid = dir.join("invalid");
+    std::fs::write(&invalid, "abcd\n")?;
+    let config = dir.join("config.yml");
+    std::fs::write(&config, format!("password_file: {}\n", password.display()))?;
+
+    // configuration file
+    let cfg = Settings::try_build(vec![config.clone()], ["test-bin"])?;
+    assert_eq!(cfg.password, "hunter2");
+    let conflict = dir.join("conflict.yml");
+    std::fs::write(
+        &conflict,
+        format!("password: x\npassword_file: {}\n", password.display()),
+    )?;
+    let err = Settings::try_build(vec![conflict], ["test-bin"]).unwrap_err();
+    assert!(
+        matches!(&err, SettingsError::SecretFile { path, .. } if *path == password),
+        "{err:?}"
+    );
+    let source = std::error::Error::source(&err).map(ToString::to_string);
+    assert_eq!(
+        source.as_deref(),
+        Some("conflicting with the key 'password'")
+    );
+
+    // command line
+    let cfg = Settings::try_build(
+        Vec::new(),
+        [
+            "test-bin".as_ref(),
+            "--password-file".as_ref(),
+            password.as_os_str(),
+        ],
+    )?;
+    assert_eq!(cfg.password, "hunter2");
+    let err = Settings::try_build(
+        Vec::new(),
+        [
+            "test-bin".as_ref(),
+            "--password=x".as_ref(),
+            "--password-file".as_ref(),
+            password.as_os_str(),
+        ],
+    )
+    .unwrap_err();
+    assert!(matches!(err, SettingsError::Cli(_)), "{err:?}");
+
+    // environment, parsed with the field type
+    std::env::set_var("CLI_SETTINGS_TEST_SECRET_PIN_FILE", &pin);
+    let (cfg, sources) = Settings::try_build_with_sources(Vec::new(), ["test-bin"])?;
+    assert_eq!(cfg.pin, 1234);
+    assert_eq!(
+        sources.get("pin"),
+        Some(&SettingsSource::Env(
+            "CLI_SETTINGS_TEST_SECRET_PIN_FILE".to_string()
+        ))
+    );
+    std::env::set_var("CLI_SETTINGS_TEST_SECRET_PIN", "5678");
+    let err = Settings::try_build(Vec::new(), ["test-bin"]).unwrap_err();
+    std::env::remove_var("CLI_SETTINGS_TEST_SECRET_PIN");
+    assert!(
+        matches!(&err, SettingsError::SecretFile { path, .. } if *path == pin),
+        "{err:?}"
+    );
+    let source = std::error::Error::source(&err).map(ToString::to_string);
+    assert_eq!(
+        source.as_deref(),
+        Some("conflicting with the environment variable 'CLI_SETTINGS_TEST_SECRET_PIN'")
+    );
+    std::env::set_var("CLI_SETTINGS_TEST_SECRET_PIN_FILE", &invalid);
+    let err = Settings::try_build(Vec::new(), ["test-bin"]).unwrap_err();
+    std::env::remove_var("CLI_SETTINGS_TEST_SECRET_PIN_FILE");
+    assert_eq!(
+        err.to_string(),
+        format!("Failed to read the secret file '{}'", invalid.display())
+    );
+
+    // missing file
+    let missing = dir.join("missing");
+    let err = Settings::try_build(
+        Vec::new(),
+        [
+            "test-bin".as_ref(),
+            "--password-file".as_ref(),
+            missing.as_os_str(),
+        ],
+    )
+    .unwrap_err();
+    assert!(
+        matches!(&err, SettingsError::SecretFile { path, .. } if *path == missing),
+        "{err:?}"
+    );
+    Ok(())
+}
+
+/// Test the invalid value of a secret field, not output by the parse error
+#[test]
+pub fn secret_parse_error() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("secret");
+    std::fs::create_dir_all(&dir)?;
+    let config = dir.join("invalid.yml");
+    std::fs::write(&config, "user: admin\npin: hunter2\n")?;
+
+    let err = Settings::try_build(vec![config.clone()], ["test-bin"]).unwrap_err();
+    assert!(
+        matches!(&err, SettingsError::FileParse { path, line: None, .. } if *path == config),
+        "{err:?}"
+    );
+    let source = std::error::Error::source(&err).map(ToString::to_string);
+    assert_eq!(
+        source.as_deref(),
+        Some("invalid value of the secret field 'pin'")
+    );
+    assert!(!format!("{err:?}").contains("hunter2"), "{err:?}");
+    Ok(())
+}
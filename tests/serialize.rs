@@ -0,0 +1,90 @@
+//! Test serialization of the settings to a configuration file
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[serde_with::serde_as]#[derive(serde::Deserialize, serde::Serialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_serialize]
+pub struct Settings {
+    /// alpha setting explanation
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub alpha: bool,
+
+    /// beta setting explanation, not written as only settable from command line
+    #[cli_settings_default = "\"beta default value\".to_string()"]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub beta: String,
+
+    /// gamma setting explanation
+    #[cli_settings_default = "1 << 40"]
+    #[cli_settings_file]
+    pub gamma: u64,
+
+    /// names setting explanation
+    #[cli_settings_file]
+    pub names: Vec<String>,
+}
+
+/// Test `to_file_string()` method
+#[test]
+pub fn to_file_string() -> anyhow::Result<()> {
+    let cfg = Settings::build(Vec::new(), ["test-bin", "--beta=something"])?;
+    assert_eq!(
+        cfg.to_file_string(SettingsFormat::Yaml)?,
+        "alpha: false\ngamma: 1099511627776\nnames: []\n"
+    );
+    Ok(())
+}
+
+/// Test `write_config()` method
+/// The written file can be loaded back
+#[test]
+pub fn write_config() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("serialize");
+    std::fs::create_dir_all(&dir)?;
+
+    let cfg = Settings {
+        alpha: true,
+        gamma: 3,
+        names: vec!["a".to_string(), "b".to_string()],
+        ..Default::default()
+    };
+
+    // formats that are not enabled fall back to YAML
+    for ext in ["yml", "toml", "json", "json5"] {
+        let path = dir.join(format!("config.{ext}"));
+        cfg.write_config(&path)?;
+        let loaded = Settings::build(vec![path], ["test-bin"])?;
+        assert_eq!(loaded, cfg);
+    }
+    Ok(())
+}
+
+/// Test `sample_config()` method
+/// The sample is documented, and can be loaded back
+#[test]
+pub fn sample_config() -> anyhow::Result<()> {
+    assert_eq!(
+        Settings::sample_config(SettingsFormat::Yaml)?,
+        "# alpha setting explanation\nalpha: false\n\n# gamma setting explanation\ngamma: 1099511627776\n\n# names setting explanation\nnames: []\n"
+    );
+
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("serialize");
+    std::fs::create_dir_all(&dir)?;
+    // formats that are not enabled fall back to YAML
+    for ext in ["yml", "toml", "json", "json5"] {
+        let path = dir.join(format!("sample.{ext}"));
+        let format = SettingsFormat::from_path(&path).expect("default format");
+        std::fs::write(&path, Settings::sample_config(format)?)?;
+        let loaded = Settings::build(vec![path], ["test-bin"])?;
+        assert_eq!(loaded, Settings::default());
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Test strict mode, reporting the unknown keys of the configuration files
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_nested]
+#[cli_settings_file = "#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Args)]"]
+pub struct Database {
+    /// database host
+    #[cli_settings_file]
+    pub host: String,
+
+    /// connection labels, with free keys
+    #[cli_settings_file]
+    pub labels: std::collections::BTreeMap<String, String>,
+}
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_strict]
+pub struct Settings {
+    /// alpha setting explanation
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub alpha: u32,
+
+    /// maximum count, renamed in the configuration file
+    #[cli_settings_file = "#[serde(rename = \"max-count\")]"]
+    pub max_count: u32,
+
+    /// database settings
+    #[cli_settings_nested]
+    #[cli_settings_file]
+    pub database: Database,
+}
+
+/// Settings only warning about the unknown keys
+mod warn {
+    #[derive(PartialEq, Debug)]
+    #[cli_settings]
+    #[cli_settings_file = "#[derive(serde::Deserialize)]"]
+    #[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+    #[cli_settings_strict = "warn"]
+    pub struct Settings {
+        /// alpha setting explanation
+        #[cli_settings_file]
+        #[cli_settings_clap = "#[arg(long)]"]
+        pub alpha: u32,
+
+        /// maximum count, renamed in the configuration file
+        #[cli_settings_file = "#[serde(rename = \"max-count\")]"]
+        pub max_count: u32,
+    }
+}
+
+/// Test an unknown key error, with a suggestion
+#[test]
+pub fn unknown_key() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let path = std::path::Path::new(&project_dir).join("tests/strict.yml");
+
+    let err = Settings::try_build(vec![path.clone()], ["test-bin"]).unwrap_err();
+    assert!(matches!(
+        &err,
+        SettingsError::UnknownKey { path: p, key, suggestion }
+            if *p == path && key == "alpah" && suggestion.as_deref() == Some("alpha")
+    ));
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Unknown key 'alpah' in the configuration file '{}', did you mean 'alpha'?",
+            path.display()
+        )
+    );
+    Ok(())
+}
+
+/// Test an unknown key in a nested section, the keys of a map field being free
+#[test]
+pub fn unknown_nested_key() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let path = std::path::Path::new(&project_dir).join("tests/strict_nested.yml");
+
+    let err = Settings::try_build(vec![path.clone()], ["test-bin"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Unknown key 'database.hots' in the configuration file '{}', did you mean 'database.host'?",
+            path.display()
+        )
+    );
+
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("strict");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("valid.yml");
+    std::fs::write(&path, "database:\n  host: db\n  labels:\n    region: eu\n")?;
+    let cfg = Settings::try_build(vec![path], ["test-bin"])?;
+    assert_eq!(cfg.database.labels["region"], "eu");
+    Ok(())
+}
+
+/// Test the report of the unknown keys, without error
+#[test]
+pub fn unknown_keys_warn() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let path = std::path::Path::new(&project_dir).join("tests/strict.yml");
+
+    let (cfg, sources) = warn::Settings::build_with_sources(vec![path.clone()], ["test-bin"])?;
+    assert_eq!(
+        cfg,
+        warn::Settings {
+            alpha: 3,
+            max_count: 10
+        }
+    );
+    assert_eq!(
+        sources.unknown_keys(),
+        [
+            (path.clone(), "alpah".to_string()),
+            (path, "zzz".to_string())
+        ]
+    );
+    Ok(())
+}
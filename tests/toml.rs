@@ -0,0 +1,120 @@
+//! Test usage of `cli_settings` with TOML configuration files
+
+#![cfg(all(feature = "yaml", feature = "toml"))]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[serde_with::serde_as]#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_format = "toml"]
+pub struct Settings {
+    /// gamma setting explanation
+    #[cli_settings_default = "1 << 63"]
+    #[cli_settings_file]
+    pub gamma: u64,
+
+    /// delta setting explanation
+    #[cli_settings_default = "42"]
+    #[cli_settings_file]
+    pub delta: u32,
+
+    /// epsilon setting explanation
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(short, long)]"]
+    pub epsilon: u32,
+}
+
+/// Settings supporting only TOML, whatever the enabled features
+mod toml_only {
+    #[derive(PartialEq, Debug)]
+    #[cli_settings]
+    #[cli_settings_file = "#[derive(serde::Deserialize)]"]
+    #[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+    #[cli_settings_formats = "toml"]
+    pub struct TomlOnly {
+        /// delta setting explanation
+        #[cli_settings_file]
+        pub delta: u32,
+    }
+}
+
+/// Test `build()` method
+/// Mix TOML and YAML files, the format being selected from the file extension
+#[test]
+pub fn build() -> anyhow::Result<()> {
+    let expected = Settings {
+        gamma: 728,    // set by TOML file
+        delta: 32,     // set by TOML and YAML files
+        epsilon: 1024, // set by TOML file and command line
+    };
+
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let test_dir = std::path::Path::new(&project_dir).join("tests");
+
+    let cfg = Settings::build(
+        vec![test_dir.join("usage.toml"), test_dir.join("usage2.yml")],
+        ["test-bin", "-e", "1024"],
+    )?;
+    assert_eq!(cfg, expected);
+    Ok(())
+}
+
+/// Test the default format, used for unknown extensions
+#[test]
+pub fn default_format() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("toml");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("config");
+    std::fs::write(&path, "delta = 12\n")?;
+
+    let cfg = Settings::build(vec![path], ["test-bin"])?;
+    assert_eq!(cfg.delta, 12);
+    Ok(())
+}
+
+/// Test the location of parse errors, computed from the span reported by TOML
+#[test]
+pub fn parse_error() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("toml");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("invalid.toml");
+    std::fs::write(&path, "gamma = 12\ndelta = \"text\"\n")?;
+
+    let err = Settings::build(vec![path], ["test-bin"]).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            SettingsError::FileParse {
+                line: Some(2),
+                column: Some(9),
+                ..
+            }
+        ),
+        "{err}"
+    );
+    Ok(())
+}
+
+/// Test the formats limited by `cli_settings_formats`, a YAML file being parsed as TOML
+#[test]
+pub fn limited_formats() -> anyhow::Result<()> {
+    let path = std::path::Path::new("config.yml");
+    let format = toml_only::TomlOnlyFormat::from_path(path);
+    // exhaustive match, TOML being the only variant
+    let name = format.map(|format| match format {
+        toml_only::TomlOnlyFormat::Toml => "toml",
+    });
+    assert_eq!(name, Some("toml"));
+
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("toml");
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("toml-only.yml");
+    std::fs::write(&path, "delta = 12\n")?;
+    let cfg = toml_only::TomlOnly::build(vec![path], ["test-bin"])?;
+    assert_eq!(cfg.delta, 12);
+    Ok(())
+}
@@ -1,5 +1,6 @@
 //! Test usage of `cli_settings` with a simple but exhaustive example
 
+#![cfg(feature = "yaml")]
 #![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
 
 #[macro_use]
@@ -75,3 +76,158 @@ pub fn build() -> anyhow::Result<()> {
     assert_eq!(cfg, expected);
     Ok(())
 }
+
+/// Settings requiring an argument, clap showing the help otherwise
+mod required {
+    #[cli_settings]
+    #[cli_settings_file = "#[derive(serde::Deserialize)]"]
+    #[cli_settings_clap = "#[derive(clap::Parser)]#[command(arg_required_else_help = true)]"]
+    pub struct Required {
+        /// alpha setting explanation
+        #[cli_settings_clap = "#[arg(long)]"]
+        pub alpha: u32,
+    }
+}
+
+/// Test `try_build()` method
+/// Command line errors and help requests are returned instead of exiting the process
+#[test]
+pub fn try_build() {
+    let res = Settings::try_build(Vec::new(), ["test-bin", "--help"]);
+    assert!(matches!(res, Err(SettingsError::Help(_))));
+
+    let res = Settings::try_build(Vec::new(), ["test-bin", "--version"]);
+    assert!(matches!(res, Err(SettingsError::Help(_))));
+
+    let res = Settings::try_build(Vec::new(), ["test-bin", "--unknown"]);
+    assert!(matches!(res, Err(SettingsError::Cli(_))));
+
+    // help shown for a missing argument, a usage error
+    let res = required::Required::try_build(Vec::new(), ["test-bin"]);
+    assert!(matches!(res, Err(required::RequiredError::Cli(_))));
+
+    let res = Settings::try_build(Vec::new(), ["test-bin", "-e", "1024"]);
+    assert_eq!(res.map(|cfg| cfg.epsilon).ok(), Some(1024));
+}
+
+/// Test errors of `build()` method
+/// Each kind of failure is reported with its own variant
+#[test]
+pub fn build_error() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("usage_build_error");
+    // start from an empty directory, as all its files are loaded
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join("invalid.yml");
+    std::fs::write(&path, "gamma: 12\ndelta: text\n")?;
+
+    let err = Settings::build(vec![path.clone()], ["test-bin"]).unwrap_err();
+    assert!(
+        matches!(err, SettingsError::FileParse { path: ref p, line: Some(2), column: Some(8), .. } if *p == path),
+        "{err}"
+    );
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Failed to parse the configuration file '{}' at line 2, column 8",
+            path.display()
+        )
+    );
+
+    // the files of a directory are loaded
+    let err = Settings::build(vec![dir], ["test-bin"]).unwrap_err();
+    assert!(
+        matches!(err, SettingsError::FileParse { path: ref p, .. } if *p == path),
+        "{err}"
+    );
+    Ok(())
+}
+
+/// Test required and optional configuration files
+#[test]
+pub fn required_file() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let test_dir = std::path::Path::new(&project_dir).join("tests");
+    let missing = test_dir.join("usage-does-not-exist.yml");
+
+    let cfg = Settings::build_with_config_files(
+        vec![
+            SettingsConfigFile::required(test_dir.join("usage1.yml")),
+            SettingsConfigFile::optional(&missing),
+        ],
+        ["test-bin"],
+    )?;
+    assert_eq!(cfg.gamma, 728);
+
+    // plain paths are optional
+    let (cfg, _) = Settings::try_build_with_config_files(
+        [missing.to_str().unwrap_or_default()],
+        ["test-bin"],
+    )?;
+    assert_eq!(cfg.gamma, 1 << 63);
+
+    let err = Settings::build_with_config_files(
+        vec![SettingsConfigFile::required(&missing)],
+        ["test-bin"],
+    )
+    .unwrap_err();
+    assert!(
+        matches!(err, SettingsError::FileOpen { path: ref p, ref source } if *p == missing && source.kind() == std::io::ErrorKind::NotFound),
+        "{err}"
+    );
+    Ok(())
+}
+
+/// Test a directory of configuration files, loaded in lexical order
+#[test]
+pub fn build_dir() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let test_dir = std::path::Path::new(&project_dir).join("tests");
+
+    let (cfg, sources) = Settings::build_with_sources(
+        vec![test_dir.join("usage.d"), test_dir.join("usage2.yml")],
+        ["test-bin"],
+    )?;
+    assert_eq!(cfg.gamma, 1);
+    assert_eq!(cfg.delta, 32);
+    assert_eq!(cfg.epsilon, 512);
+    assert_eq!(
+        sources.get("gamma"),
+        Some(&SettingsSource::File(test_dir.join("usage.d/10-first.yml")))
+    );
+
+    let cfg = Settings::build(vec![test_dir.join("usage.d")], ["test-bin"])?;
+    assert_eq!(cfg.delta, 2);
+    Ok(())
+}
+
+/// Test `build_with_sources()` method
+/// The source of each field is the last layer that provided its value
+#[test]
+pub fn build_with_sources() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let test_dir = std::path::Path::new(&project_dir).join("tests");
+
+    let (_, sources) = Settings::build_with_sources(
+        vec![test_dir.join("usage1.yml"), test_dir.join("usage2.yml")],
+        ["test-bin", "--beta=something"],
+    )?;
+    assert_eq!(
+        sources.iter().collect::<Vec<_>>(),
+        vec![
+            ("alpha", &SettingsSource::Default),
+            ("beta", &SettingsSource::Cli),
+            ("gamma", &SettingsSource::File(test_dir.join("usage1.yml"))),
+            ("delta", &SettingsSource::File(test_dir.join("usage2.yml"))),
+            (
+                "epsilon",
+                &SettingsSource::File(test_dir.join("usage2.yml"))
+            ),
+        ]
+    );
+    assert_eq!(sources.get("beta"), Some(&SettingsSource::Cli));
+    assert_eq!(sources.get("unknown"), None);
+    Ok(())
+}
@@ -0,0 +1,150 @@
+//! Test the checks of the setting values, once all sources are merged
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug)]
+#[cli_settings]
+#[cli_settings_file = "#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_validate_with = "Settings::check"]
+pub struct Settings {
+    /// listening port
+    #[cli_settings_default = "8080"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    #[cli_settings_validate = "range(1..=65535)"]
+    pub port: u32,
+
+    /// server name
+    #[cli_settings_default = "\"server\".to_string()"]
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    #[cli_settings_validate = "non_empty, is_lowercase"]
+    pub name: String,
+
+    /// log level
+    #[cli_settings_default = "\"info\".to_string()"]
+    #[cli_settings_clap = "#[arg(long)]"]
+    #[cli_settings_validate = "one_of(\"debug\", \"info\", \"error\")"]
+    pub level: String,
+
+    /// data directory
+    #[cli_settings_default = "std::path::PathBuf::from(\"/\")"]
+    #[cli_settings_clap = "#[arg(long)]"]
+    #[cli_settings_validate = "path_exists"]
+    pub data_dir: std::path::PathBuf,
+
+    /// minimum number of workers
+    #[cli_settings_default = "1"]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub min_workers: u32,
+
+    /// maximum number of workers
+    #[cli_settings_default = "4"]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub max_workers: u32,
+}
+
+impl Settings {
+    /// Check the consistency of the settings
+    fn check(&self) -> Result<(), &'static str> {
+        if self.min_workers > self.max_workers {
+            return Err("min_workers shall not be greater than max_workers");
+        }
+        Ok(())
+    }
+}
+
+/// Custom check of the server name
+fn is_lowercase(name: &str) -> Result<(), String> {
+    if name.chars().any(char::is_uppercase) {
+        Err(format!("'{name}' is not lowercase"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Get the reason of a validation error
+fn reason(err: &SettingsError) -> String {
+    std::error::Error::source(err).unwrap().to_string()
+}
+
+/// Test valid values
+#[test]
+pub fn valid() -> anyhow::Result<()> {
+    let cfg = Settings::try_build(Vec::new(), ["test-bin", "--port=1", "--level=debug"])?;
+    assert_eq!(cfg.port, 1);
+    assert_eq!(cfg.level, "debug");
+    Ok(())
+}
+
+/// Test the built-in checks, reporting the field and the source of the value
+#[test]
+pub fn invalid() -> anyhow::Result<()> {
+    let project_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+    let path = std::path::Path::new(&project_dir).join("tests/validate.yml");
+
+    let err = Settings::try_build(vec![path.clone()], ["test-bin"]).unwrap_err();
+    assert!(matches!(
+        &err,
+        SettingsError::Validation { field, origin: SettingsSource::File(p), .. }
+            if field == "port" && *p == path
+    ));
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Invalid value of the setting 'port' from the configuration file '{}'",
+            path.display()
+        )
+    );
+    assert_eq!(reason(&err), "shall be in the range 1..=65535");
+
+    let err = Settings::try_build(Vec::new(), ["test-bin", "--name="]).unwrap_err();
+    assert!(matches!(
+        &err,
+        SettingsError::Validation { field, origin: SettingsSource::Cli, .. } if field == "name"
+    ));
+    assert_eq!(reason(&err), "shall not be empty");
+
+    let err = Settings::try_build(Vec::new(), ["test-bin", "--level=trace"]).unwrap_err();
+    assert_eq!(
+        reason(&err),
+        "shall be one of \"debug\", \"info\", \"error\""
+    );
+
+    let err =
+        Settings::try_build(Vec::new(), ["test-bin", "--data-dir=/does/not/exist"]).unwrap_err();
+    assert_eq!(reason(&err), "shall be an existing path");
+    Ok(())
+}
+
+/// Test a custom check
+#[test]
+pub fn custom() {
+    let err = Settings::try_build(Vec::new(), ["test-bin", "--name=Server"]).unwrap_err();
+    assert!(matches!(
+        &err,
+        SettingsError::Validation { field, origin: SettingsSource::Cli, .. } if field == "name"
+    ));
+    assert_eq!(reason(&err), "'Server' is not lowercase");
+}
+
+/// Test the check of the whole settings
+#[test]
+pub fn validate_with() -> anyhow::Result<()> {
+    let cfg = Settings::try_build(Vec::new(), ["test-bin", "--min-workers=4"])?;
+    assert_eq!(cfg.min_workers, 4);
+
+    let err = Settings::try_build(Vec::new(), ["test-bin", "--min-workers=5"]).unwrap_err();
+    assert!(matches!(err, SettingsError::InvalidSettings { .. }));
+    assert_eq!(err.to_string(), "Invalid settings");
+    assert_eq!(
+        reason(&err),
+        "min_workers shall not be greater than max_workers"
+    );
+    Ok(())
+}
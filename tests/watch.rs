@@ -0,0 +1,173 @@
+//! Test the watch of the configuration files
+
+#![cfg(feature = "yaml")]
+#![allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+
+#[macro_use]
+extern crate cli_settings_derive;
+
+#[derive(PartialEq, Debug, Clone)]
+#[cli_settings]
+#[cli_settings_file = "#[derive(serde::Deserialize)]"]
+#[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+#[cli_settings_watch = "20"]
+pub struct Settings {
+    /// gamma setting explanation
+    #[cli_settings_file]
+    pub gamma: u64,
+
+    /// delta setting explanation
+    #[cli_settings_file]
+    #[cli_settings_clap = "#[arg(long)]"]
+    pub delta: u32,
+}
+
+/// Replace the content of the file at once, not to get a partial file when polled
+fn write(file: &std::path::Path, content: &str) -> std::io::Result<()> {
+    let tmp = file.with_extension("tmp");
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(tmp, file)
+}
+
+/// Test the settings built again on a file change and on a reload request
+///
+/// The successive contents have different lengths, as the quick writes may get the same modification time
+#[test]
+pub fn watch() -> anyhow::Result<()> {
+    let file = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("watch.yml");
+    write(&file, "gamma: 1\ndelta: 2\n")?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let (cfg, watcher) = Settings::watch(
+        vec![file.clone()],
+        ["test-bin", "--delta", "42"],
+        move |change| {
+            let change = change
+                .map(|(old, new)| (old.gamma, new.gamma, new.delta))
+                .map_err(|err| err.to_string());
+            sender.send(change).unwrap();
+        },
+    )?;
+    assert_eq!(
+        cfg,
+        Settings {
+            gamma: 1,
+            delta: 42
+        }
+    );
+    let timeout = std::time::Duration::from_secs(10);
+
+    // file change, the command line arguments staying on top
+    write(&file, "gamma: 30\ndelta: 4\n")?;
+    assert_eq!(receiver.recv_timeout(timeout)?, Ok((1, 30, 42)));
+
+    // invalid file, keeping the previous settings
+    write(&file, "gamma: [\n")?;
+    let err = receiver.recv_timeout(timeout)?.unwrap_err();
+    assert!(
+        err.starts_with("Failed to parse the configuration file"),
+        "{err}"
+    );
+
+    write(&file, "gamma: 500\n")?;
+    assert_eq!(receiver.recv_timeout(timeout)?, Ok((30, 500, 42)));
+
+    // reload request
+    watcher.reload();
+    assert_eq!(receiver.recv_timeout(timeout)?, Ok((500, 500, 42)));
+
+    // no change
+    assert!(receiver
+        .recv_timeout(std::time::Duration::from_millis(200))
+        .is_err());
+    Ok(())
+}
+
+/// Settings with the configuration files given on the command line, including other files
+mod resolved {
+    #[derive(PartialEq, Debug, Clone)]
+    #[cli_settings]
+    #[cli_settings_file = "#[derive(serde::Deserialize)]"]
+    #[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+    #[cli_settings_config_arg]
+    #[cli_settings_include]
+    #[cli_settings_watch = "20"]
+    pub struct Settings {
+        /// gamma setting explanation
+        #[cli_settings_file]
+        pub gamma: u64,
+
+        /// delta setting explanation
+        #[cli_settings_file]
+        pub delta: u32,
+    }
+}
+
+/// Test the watch of the files given by `--config` and of the files they include
+#[test]
+pub fn watch_resolved() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("watch_resolved");
+    std::fs::create_dir_all(&dir)?;
+    let file = dir.join("main.yml");
+    let included = dir.join("included.yml");
+    write(&file, "include: [included.yml]\ngamma: 1\n")?;
+    write(&included, "delta: 2\n")?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let (cfg, _watcher) = resolved::Settings::watch(
+        Vec::<std::path::PathBuf>::new(),
+        ["test-bin".as_ref(), "--config".as_ref(), file.as_os_str()],
+        move |change| {
+            let change = change
+                .map(|(_, new)| (new.gamma, new.delta))
+                .map_err(|err| err.to_string());
+            sender.send(change).unwrap();
+        },
+    )?;
+    assert_eq!(cfg, resolved::Settings { gamma: 1, delta: 2 });
+    let timeout = std::time::Duration::from_secs(10);
+
+    // file given on the command line
+    write(&file, "include: [included.yml]\ngamma: 30\n")?;
+    assert_eq!(receiver.recv_timeout(timeout)?, Ok((30, 2)));
+
+    // included file
+    write(&included, "delta: 400\n")?;
+    assert_eq!(receiver.recv_timeout(timeout)?, Ok((30, 400)));
+    Ok(())
+}
+
+/// Settings built again on SIGHUP
+mod sighup {
+    #[derive(PartialEq, Debug, Clone)]
+    #[cli_settings]
+    #[cli_settings_file = "#[derive(serde::Deserialize)]"]
+    #[cli_settings_clap = "#[derive(clap::Parser)]#[command(version)]"]
+    #[cli_settings_watch = "20"]
+    #[cli_settings_watch_sighup]
+    pub struct Settings {
+        /// gamma setting explanation
+        #[cli_settings_file]
+        pub gamma: u64,
+    }
+}
+
+/// Test the settings built again on SIGHUP, even if no file changed
+#[cfg(unix)]
+#[test]
+pub fn watch_sighup() -> anyhow::Result<()> {
+    let file = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("watch_sighup.yml");
+    write(&file, "gamma: 1\n")?;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let (_, _watcher) = sighup::Settings::watch(vec![file], ["test-bin"], move |change| {
+        sender
+            .send(change.map(|(old, new)| (old.gamma, new.gamma)).is_ok())
+            .unwrap();
+    })?;
+    let timeout = std::time::Duration::from_secs(10);
+
+    signal_hook::low_level::raise(signal_hook::consts::SIGHUP)?;
+    assert!(receiver.recv_timeout(timeout)?);
+    Ok(())
+}